@@ -4,8 +4,8 @@
  * Created Date: 11/11/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 08/08/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
@@ -17,6 +17,7 @@ use acoustic_field_viewer::{
     camera_helper,
     dir_viewer::{Axis3D, DirectionViewer},
     field_compute_pipeline::FieldComputePipeline,
+    focus_gizmo::FocusGizmo,
     renderer::Renderer,
     slice_viewer::SliceViewer,
     sound_sources::{Drive, SoundSources},
@@ -52,6 +53,7 @@ struct App {
     sources: SoundSources,
     axes: Vec<Axis3D>,
     focal_pos: Vector3,
+    focus_gizmo: FocusGizmo,
     last_frame: Instant,
     field_compute_pipeline: FieldComputePipeline,
     slice_viewer: SliceViewer,
@@ -90,12 +92,13 @@ impl App {
             }
         }
 
-        let axes = vec![Axis3D::new(
+        let mut axes = vec![Axis3D::new(
             [0., 0., 0.],
             [1., 0., 0.],
             [0., 1., 0.],
             [0., 0., 1.],
         )];
+        axes.extend(FocusGizmo::new().axes(&[FOCAL_POS]));
 
         let field_compute_pipeline = FieldComputePipeline::new(renderer.queue(), &viewer_settings);
         let slice_viewer = SliceViewer::new(renderer, &viewer_settings);
@@ -107,6 +110,7 @@ impl App {
             sources,
             axes,
             focal_pos: FOCAL_POS,
+            focus_gizmo: FocusGizmo::new(),
             last_frame: Instant::now(),
             field_compute_pipeline,
             slice_viewer,
@@ -132,6 +136,7 @@ impl App {
 
         self.focal_pos = FOCAL_POS;
         Self::calc_focus_phase(self.focal_pos, &mut self.sources, &self.viewer_settings);
+        self.update_focus_axis();
 
         self.field_compute_pipeline
             .update(&self.sources, UpdateFlag::all(), &self.viewer_settings);
@@ -186,8 +191,33 @@ impl App {
             .update(&self.sources, update_flag, &self.viewer_settings);
     }
 
+    fn update_focus_axis(&mut self) {
+        self.axes.truncate(1);
+        self.axes.extend(self.focus_gizmo.axes(&[self.focal_pos]));
+    }
+
+    fn update_gizmo(&mut self, ui: &Ui) -> UpdateFlag {
+        let io = ui.io();
+        let mouse_down =
+            io.mouse_down[0] && (self.focus_gizmo.is_dragging() || !io.want_capture_mouse);
+        match self.focus_gizmo.update(
+            &[self.focal_pos],
+            io.mouse_pos,
+            mouse_down,
+            io.display_size,
+            &self.view_projection,
+        ) {
+            Some((_, pos)) if pos != self.focal_pos => {
+                self.focal_pos = pos;
+                Self::calc_focus_phase(self.focal_pos, &mut self.sources, &self.viewer_settings);
+                UpdateFlag::UPDATE_SOURCE_DRIVE
+            }
+            _ => UpdateFlag::empty(),
+        }
+    }
+
     fn update_ui(&mut self, ui: &Ui, renderer: &mut Renderer) -> UpdateFlag {
-        let mut update_flag = UpdateFlag::empty();
+        let mut update_flag = self.update_gizmo(ui);
         ui.text(format!("fps: {:.1}", self.fps));
         TabBar::new("Settings").build(ui, || {
             TabItem::new("Focus").build(ui, || {
@@ -494,6 +524,7 @@ impl App {
             .field_compute_pipeline
             .compute(
                 filed_image,
+                self.slice_viewer.field(),
                 self.slice_viewer.model(),
                 &self.sources,
                 &self.viewer_settings,
@@ -518,7 +549,11 @@ impl App {
                 self.frame_count = 0;
             }
         }
-        let update_flag = self.update_ui(&ui, renderer);
+        let mut update_flag = self.update_ui(&ui, renderer);
+        if update_flag.contains(UpdateFlag::UPDATE_SOURCE_DRIVE) {
+            self.update_focus_axis();
+            update_flag |= UpdateFlag::INIT_AXIS;
+        }
         self.update_view(renderer, update_flag);
 
         let mut cmd_buf_builder = AutoCommandBufferBuilder::primary(
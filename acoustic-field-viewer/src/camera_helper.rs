@@ -4,8 +4,8 @@
  * Created Date: 26/11/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 26/11/2021
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
@@ -13,7 +13,7 @@
 
 use camera_controllers::Camera;
 
-use crate::{Matrix3, Vector3};
+use crate::{Matrix3, Matrix4, Vector2, Vector3};
 
 pub fn set_camera_angle(camera: &mut Camera<f32>, angle: Vector3) {
     let rot = quaternion::euler_angles(angle[0], angle[1], angle[2]);
@@ -37,3 +37,40 @@ pub fn rot_mat_to_euler_angles(mat: &Matrix3) -> Vector3 {
         [x, y, z]
     }
 }
+
+fn unproject(ndc: Vector3, inv: &Matrix4) -> Vector3 {
+    let p = vecmath::col_mat4_transform(*inv, [ndc[0], ndc[1], ndc[2], 1.0]);
+    [p[0] / p[3], p[1] / p[3], p[2] / p[3]]
+}
+
+// returns the origin and the normalized direction of the ray through the pixel at pos
+pub fn screen_to_ray(
+    pos: Vector2,
+    size: Vector2,
+    view_projection: &(Matrix4, Matrix4),
+) -> (Vector3, Vector3) {
+    let (view, proj) = view_projection;
+    let inv = vecmath::mat4_inv(vecmath::col_mat4_mul(*proj, *view));
+    let x = 2.0 * pos[0] / size[0] - 1.0;
+    let y = 2.0 * pos[1] / size[1] - 1.0;
+    let near = unproject([x, y, -1.0], &inv);
+    let far = unproject([x, y, 1.0], &inv);
+    (near, vecmath::vec3_normalized(vecmath::vec3_sub(far, near)))
+}
+
+pub fn world_to_screen(
+    p: Vector3,
+    size: Vector2,
+    view_projection: &(Matrix4, Matrix4),
+) -> Option<Vector2> {
+    let (view, proj) = view_projection;
+    let clip =
+        vecmath::col_mat4_transform(vecmath::col_mat4_mul(*proj, *view), [p[0], p[1], p[2], 1.0]);
+    if clip[3] <= 0.0 {
+        return None;
+    }
+    Some([
+        (clip[0] / clip[3] + 1.0) / 2.0 * size[0],
+        (clip[1] / clip[3] + 1.0) / 2.0 * size[1],
+    ])
+}
@@ -0,0 +1,181 @@
+/*
+ * File: color_map.rs
+ * Project: common
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{fs, io, path::Path};
+
+use scarlet::{
+    colormap::{ColorMap, ListedColorMap},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use super::coloring_method::coloring_hsv;
+
+pub type Rgb = [f32; 3];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMapKind {
+    Inferno,
+    Viridis,
+    Magma,
+    Plasma,
+    Turbo,
+    Grayscale,
+    Diverging,
+    Cyclic,
+    Custom,
+}
+
+impl ColorMapKind {
+    pub const ALL: [ColorMapKind; 9] = [
+        ColorMapKind::Inferno,
+        ColorMapKind::Viridis,
+        ColorMapKind::Magma,
+        ColorMapKind::Plasma,
+        ColorMapKind::Turbo,
+        ColorMapKind::Grayscale,
+        ColorMapKind::Diverging,
+        ColorMapKind::Cyclic,
+        ColorMapKind::Custom,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorMapKind::Inferno => "inferno",
+            ColorMapKind::Viridis => "viridis",
+            ColorMapKind::Magma => "magma",
+            ColorMapKind::Plasma => "plasma",
+            ColorMapKind::Turbo => "turbo",
+            ColorMapKind::Grayscale => "grayscale",
+            ColorMapKind::Diverging => "diverging (blue-red)",
+            ColorMapKind::Cyclic => "cyclic",
+            ColorMapKind::Custom => "custom",
+        }
+    }
+
+    // n colors evenly spaced over [0, 1); custom is used only for ColorMapKind::Custom
+    pub fn sample(&self, custom: &[Rgb], n: usize) -> Vec<Rgb> {
+        let iter = (0..n).map(|x| x as f64 / n as f64);
+        match self {
+            ColorMapKind::Inferno => Self::listed(ListedColorMap::inferno(), iter),
+            ColorMapKind::Viridis => Self::listed(ListedColorMap::viridis(), iter),
+            ColorMapKind::Magma => Self::listed(ListedColorMap::magma(), iter),
+            ColorMapKind::Plasma => Self::listed(ListedColorMap::plasma(), iter),
+            ColorMapKind::Turbo => iter.map(turbo).collect(),
+            ColorMapKind::Grayscale => iter.map(|t| [t as f32; 3]).collect(),
+            ColorMapKind::Diverging => iter.map(|t| diverging(t as f32)).collect(),
+            ColorMapKind::Cyclic => iter
+                .map(|t| {
+                    let c = coloring_hsv(t as f32, 1.0, 1.0);
+                    [c[0], c[1], c[2]]
+                })
+                .collect(),
+            ColorMapKind::Custom if custom.is_empty() => iter.map(|t| [t as f32; 3]).collect(),
+            ColorMapKind::Custom => iter.map(|t| interpolate(custom, t as f32)).collect(),
+        }
+    }
+
+    fn listed(map: ListedColorMap, iter: impl Iterator<Item = f64>) -> Vec<Rgb> {
+        let colors: Vec<RGBColor> = map.transform(iter);
+        colors
+            .into_iter()
+            .map(|c| [c.r as f32, c.g as f32, c.b as f32])
+            .collect()
+    }
+}
+
+// one "r, g, b" per line in [0, 1]; empty lines and lines starting with '#' are ignored
+pub fn load_custom<P: AsRef<Path>>(path: P) -> io::Result<Vec<Rgb>> {
+    let invalid = |line: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid color: {}", line),
+        )
+    };
+    fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| {
+            let v = l
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<f32>().map_err(|_| invalid(l)))
+                .collect::<io::Result<Vec<_>>>()?;
+            match v[..] {
+                [r, g, b] => Ok([r.clamp(0., 1.), g.clamp(0., 1.), b.clamp(0., 1.)]),
+                _ => Err(invalid(l)),
+            }
+        })
+        .collect()
+}
+
+fn interpolate(colors: &[Rgb], t: f32) -> Rgb {
+    if colors.len() == 1 {
+        return colors[0];
+    }
+    let x = t.clamp(0., 1.) * (colors.len() - 1) as f32;
+    let i = (x.floor() as usize).min(colors.len() - 2);
+    let f = x - i as f32;
+    let (a, b) = (colors[i], colors[i + 1]);
+    [
+        a[0] + (b[0] - a[0]) * f,
+        a[1] + (b[1] - a[1]) * f,
+        a[2] + (b[2] - a[2]) * f,
+    ]
+}
+
+// polynomial approximation of Google's turbo colormap
+fn turbo(t: f64) -> Rgb {
+    let t = t.clamp(0., 1.);
+    let poly = |c: [f64; 6]| {
+        (c[0] + t * (c[1] + t * (c[2] + t * (c[3] + t * (c[4] + t * c[5]))))).clamp(0., 1.) as f32
+    };
+    [
+        poly([
+            0.13572138,
+            4.61539260,
+            -42.66032258,
+            132.13108234,
+            -152.94239396,
+            59.28637943,
+        ]),
+        poly([
+            0.09140261,
+            2.19418839,
+            4.84296658,
+            -14.18503333,
+            4.27729857,
+            2.82956604,
+        ]),
+        poly([
+            0.10667330,
+            12.64194608,
+            -60.58204836,
+            110.36276771,
+            -89.90310912,
+            27.34824973,
+        ]),
+    ]
+}
+
+fn diverging(t: f32) -> Rgb {
+    interpolate(
+        &[
+            [0.230, 0.299, 0.754],
+            [0.865, 0.865, 0.865],
+            [0.706, 0.016, 0.150],
+        ],
+        t,
+    )
+}
@@ -4,12 +4,13 @@
  * Created Date: 01/12/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 01/12/2021
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
  */
 
 pub mod color;
+pub mod color_map;
 pub mod coloring_method;
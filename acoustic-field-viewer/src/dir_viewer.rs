@@ -4,8 +4,8 @@
  * Created Date: 01/12/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 18/05/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
@@ -148,16 +148,19 @@ impl DirectionViewer {
         axis: &[Axis3D],
         update_flag: UpdateFlag,
     ) {
-        if !axis.is_empty()
-            && (update_flag.contains(UpdateFlag::INIT_AXIS)
-                || update_flag.contains(UpdateFlag::UPDATE_AXIS_SIZE)
-                || update_flag.contains(UpdateFlag::UPDATE_AXIS_FLAG))
+        if update_flag.contains(UpdateFlag::INIT_AXIS)
+            || update_flag.contains(UpdateFlag::UPDATE_AXIS_SIZE)
+            || update_flag.contains(UpdateFlag::UPDATE_AXIS_FLAG)
         {
-            self.instance_data = Some(Self::create_instance_data(
-                renderer.device(),
-                settings,
-                axis,
-            ));
+            self.instance_data = if axis.is_empty() {
+                None
+            } else {
+                Some(Self::create_instance_data(
+                    renderer.device(),
+                    settings,
+                    axis,
+                ))
+            };
         }
 
         if update_flag.contains(UpdateFlag::UPDATE_CAMERA_POS) {
@@ -4,8 +4,8 @@
  * Created Date: 28/11/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 09/05/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
@@ -14,7 +14,6 @@
 use std::sync::Arc;
 
 use bytemuck::{Pod, Zeroable};
-use scarlet::{colormap::ColorMap, prelude::*};
 use vulkano::{
     buffer::{BufferUsage, CpuAccessibleBuffer},
     command_buffer::{AutoCommandBufferBuilder, CommandBufferUsage, PrimaryCommandBuffer},
@@ -27,9 +26,12 @@ use vulkano::{
     sync::GpuFuture,
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
+    common::color_map::Rgb,
     sound_sources::{Drive, SoundSources},
-    Matrix4, UpdateFlag, Vector4, ViewerSettings,
+    Matrix4, UpdateFlag, Vector2, Vector4, ViewerSettings,
 };
 
 #[repr(C)]
@@ -41,9 +43,39 @@ struct Config {
     width: u32,
     height: u32,
     pixel_size: u32,
-    _dummy_0: u32,
-    _dummy_1: i32,
+    db_mode: u32,
+    db_range: f32,
     world: Matrix4,
+    quantity: u32,
+    depth: u32,
+    _dummy: [u32; 2],
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliceQuantity {
+    Amplitude,
+    Phase,
+    // signed real part, best with a diverging color map
+    Real,
+    Intensity,
+}
+
+impl SliceQuantity {
+    pub const ALL: [SliceQuantity; 4] = [
+        SliceQuantity::Amplitude,
+        SliceQuantity::Phase,
+        SliceQuantity::Real,
+        SliceQuantity::Intensity,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SliceQuantity::Amplitude => "amplitude",
+            SliceQuantity::Phase => "phase",
+            SliceQuantity::Real => "real part",
+            SliceQuantity::Intensity => "intensity",
+        }
+    }
 }
 
 pub struct FieldComputePipeline {
@@ -52,6 +84,7 @@ pub struct FieldComputePipeline {
     source_pos_buf: Option<Arc<CpuAccessibleBuffer<[[f32; 4]]>>>,
     source_drive_buf: Option<Arc<CpuAccessibleBuffer<[Drive]>>>,
     color_map_desc_set: Arc<PersistentDescriptorSet>,
+    custom_color_map: Vec<Rgb>,
 }
 
 impl FieldComputePipeline {
@@ -69,26 +102,31 @@ impl FieldComputePipeline {
         };
 
         let color_map_desc_set =
-            Self::create_color_map_desc_set(queue.clone(), pipeline.clone(), settings);
+            Self::create_color_map_desc_set(queue.clone(), pipeline.clone(), settings, &[]);
         Self {
             queue,
             pipeline,
             source_pos_buf: None,
             source_drive_buf: None,
             color_map_desc_set,
+            custom_color_map: vec![],
         }
     }
 
+    // used when ColorMapKind::Custom is selected; takes effect on the next UPDATE_COLOR_MAP
+    pub fn set_custom_color_map(&mut self, colors: Vec<Rgb>) {
+        self.custom_color_map = colors;
+    }
+
     fn create_color_map_desc_set(
         queue: Arc<Queue>,
         pipeline: Arc<ComputePipeline>,
         settings: &ViewerSettings,
+        custom: &[Rgb],
     ) -> Arc<PersistentDescriptorSet> {
         let color_map_size = 100;
-        let iter = (0..color_map_size).map(|x| x as f64 / color_map_size as f64);
         let (texture, _) = {
-            let color_map: Vec<RGBColor> =
-                scarlet::colormap::ListedColorMap::inferno().transform(iter);
+            let color_map = settings.color_map.sample(custom, color_map_size as usize);
             let dimensions = ImageDimensions::Dim1d {
                 width: color_map_size,
                 array_layers: 1,
@@ -96,9 +134,9 @@ impl FieldComputePipeline {
             let alpha = (settings.slice_alpha * 255.) as u8;
             let mut texels = Vec::with_capacity(color_map.len());
             for color in color_map {
-                texels.push((color.r * 255.) as u8);
-                texels.push((color.g * 255.) as u8);
-                texels.push((color.b * 255.) as u8);
+                texels.push((color[0] * 255.) as u8);
+                texels.push((color[1] * 255.) as u8);
+                texels.push((color[2] * 255.) as u8);
                 texels.push(alpha);
             }
             let (image, future) = ImmutableImage::from_iter(
@@ -156,6 +194,7 @@ impl FieldComputePipeline {
                 self.queue.clone(),
                 self.pipeline.clone(),
                 settings,
+                &self.custom_color_map,
             );
         }
     }
@@ -163,15 +202,48 @@ impl FieldComputePipeline {
     pub fn compute(
         &mut self,
         image: Arc<CpuAccessibleBuffer<[Vector4]>>,
+        field: Arc<CpuAccessibleBuffer<[Vector2]>>,
         slice_model: &Matrix4,
         sources: &SoundSources,
         settings: &ViewerSettings,
+    ) -> Box<dyn GpuFuture> {
+        let size = [
+            settings.slice_width / settings.slice_pixel_size,
+            settings.slice_height / settings.slice_pixel_size,
+            1,
+        ];
+        self.compute_grid(
+            image,
+            field,
+            slice_model,
+            size,
+            settings.slice_pixel_size,
+            sources,
+            settings,
+        )
+    }
+
+    // computes a size[0] x size[1] x size[2] grid with spacing pixel_size in the model coordinate,
+    // centered at the origin except that layer size[2] / 2 (integer division) lies on z = 0
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_grid(
+        &mut self,
+        image: Arc<CpuAccessibleBuffer<[Vector4]>>,
+        field: Arc<CpuAccessibleBuffer<[Vector2]>>,
+        model: &Matrix4,
+        size: [u32; 3],
+        pixel_size: u32,
+        sources: &SoundSources,
+        settings: &ViewerSettings,
     ) -> Box<dyn GpuFuture> {
         let pipeline_layout = self.pipeline.layout();
         let desc_layout = pipeline_layout.set_layouts().get(0).unwrap();
         let set = PersistentDescriptorSet::new(
             desc_layout.clone(),
-            [WriteDescriptorSet::buffer(0, image)],
+            [
+                WriteDescriptorSet::buffer(0, image),
+                WriteDescriptorSet::buffer(1, field),
+            ],
         )
         .unwrap();
         let mut builder = AutoCommandBufferBuilder::primary(
@@ -187,12 +259,15 @@ impl FieldComputePipeline {
                 source_num,
                 _wave_num: 0.0,
                 color_scale: settings.color_scale,
-                width: settings.slice_width / settings.slice_pixel_size,
-                height: settings.slice_height / settings.slice_pixel_size,
-                pixel_size: settings.slice_pixel_size,
-                _dummy_0: 0,
-                _dummy_1: 0,
-                world: *slice_model,
+                width: size[0],
+                height: size[1],
+                pixel_size,
+                db_mode: settings.db_mode as u32,
+                db_range: settings.db_range,
+                world: *model,
+                quantity: settings.slice_quantity as u32,
+                depth: size[2],
+                _dummy: [0; 2],
             };
             CpuAccessibleBuffer::from_data(
                 self.queue.device().clone(),
@@ -237,11 +312,7 @@ impl FieldComputePipeline {
                 0,
                 (set, set_1, set_2, set_3, self.color_map_desc_set.clone()),
             )
-            .dispatch([
-                (settings.slice_width / settings.slice_pixel_size - 1) / 32 + 1,
-                (settings.slice_height / settings.slice_pixel_size - 1) / 32 + 1,
-                1,
-            ])
+            .dispatch([(size[0] - 1) / 32 + 1, (size[1] - 1) / 32 + 1, size[2]])
             .unwrap();
         let command_buffer = builder.build().unwrap();
         let finished = command_buffer.execute(self.queue.clone()).unwrap();
@@ -0,0 +1,107 @@
+/*
+ * File: focus_gizmo.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use crate::{camera_helper, dir_viewer::Axis3D, Matrix4, Vector2, Vector3};
+
+struct DragState {
+    idx: usize,
+    normal: Vector3,
+    offset: Vector3,
+}
+
+pub struct FocusGizmo {
+    pub pick_radius: f32,
+    pressed: bool,
+    drag: Option<DragState>,
+}
+
+impl FocusGizmo {
+    pub fn new() -> Self {
+        Self {
+            pick_radius: 10.0,
+            pressed: false,
+            drag: None,
+        }
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    // picks a point when the button goes down and moves it in the plane facing the camera while the button is held
+    // returns the index and the new position of the dragged point
+    pub fn update(
+        &mut self,
+        points: &[Vector3],
+        mouse_pos: Vector2,
+        mouse_down: bool,
+        display_size: Vector2,
+        view_projection: &(Matrix4, Matrix4),
+    ) -> Option<(usize, Vector3)> {
+        let just_pressed = mouse_down && !self.pressed;
+        self.pressed = mouse_down;
+        if !mouse_down {
+            self.drag = None;
+            return None;
+        }
+
+        let (origin, dir) = camera_helper::screen_to_ray(mouse_pos, display_size, view_projection);
+        if just_pressed {
+            self.drag = points
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &p)| {
+                    let s = camera_helper::world_to_screen(p, display_size, view_projection)?;
+                    let d = vecmath::vec2_len(vecmath::vec2_sub(s, mouse_pos));
+                    (d < self.pick_radius).then_some((i, d))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .and_then(|(idx, _)| {
+                    let hit = Self::intersect(origin, dir, points[idx], dir)?;
+                    Some(DragState {
+                        idx,
+                        normal: dir,
+                        offset: vecmath::vec3_sub(points[idx], hit),
+                    })
+                });
+            return None;
+        }
+
+        let drag = self.drag.as_ref()?;
+        let p = *points.get(drag.idx)?;
+        let hit = Self::intersect(origin, dir, p, drag.normal)?;
+        Some((drag.idx, vecmath::vec3_add(hit, drag.offset)))
+    }
+
+    pub fn axes(&self, points: &[Vector3]) -> Vec<Axis3D> {
+        points
+            .iter()
+            .map(|&p| Axis3D::new(p, [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]))
+            .collect()
+    }
+
+    fn intersect(origin: Vector3, dir: Vector3, p: Vector3, normal: Vector3) -> Option<Vector3> {
+        let denom = vecmath::vec3_dot(dir, normal);
+        if denom.abs() < 1e-6 {
+            return None;
+        }
+        let t = vecmath::vec3_dot(vecmath::vec3_sub(p, origin), normal) / denom;
+        Some(vecmath::vec3_add(origin, vecmath::vec3_scale(dir, t)))
+    }
+}
+
+impl Default for FocusGizmo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -4,8 +4,8 @@
  * Created Date: 17/12/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 09/05/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2022 Hapis Lab. All rights reserved.
  *
@@ -15,12 +15,14 @@ pub mod camera_helper;
 pub mod common;
 pub mod dir_viewer;
 pub mod field_compute_pipeline;
+pub mod focus_gizmo;
 pub mod renderer;
 pub mod slice_viewer;
 pub mod sound_sources;
 pub mod trans_viewer;
 mod update_flag;
 mod viewer_settings;
+pub mod volume_viewer;
 
 pub use update_flag::UpdateFlag;
 pub use viewer_settings::ViewerSettings;
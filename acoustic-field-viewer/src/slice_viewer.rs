@@ -4,8 +4,8 @@
  * Created Date: 11/11/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 09/05/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
@@ -32,8 +32,8 @@ use vulkano::{
 };
 
 use crate::{
-    renderer::Renderer, update_flag::UpdateFlag, viewer_settings::ViewerSettings, Matrix4, Vector3,
-    Vector4,
+    renderer::Renderer, update_flag::UpdateFlag, viewer_settings::ViewerSettings, Matrix4, Vector2,
+    Vector3, Vector4,
 };
 
 pub type FieldImageView = Arc<ImageView<Arc<StorageImage>>>;
@@ -87,6 +87,7 @@ pub struct SliceViewer {
     view_projection: (Matrix4, Matrix4),
     model: Matrix4,
     field_image_view: Arc<CpuAccessibleBuffer<[Vector4]>>,
+    field: Arc<CpuAccessibleBuffer<[Vector2]>>,
     slice_size: [u32; 2],
 }
 
@@ -115,6 +116,7 @@ impl SliceViewer {
         let width = settings.slice_width / settings.slice_pixel_size;
         let height = settings.slice_height / settings.slice_pixel_size;
         let field_image_view = Self::create_field_image_view(renderer, [width, height]);
+        let field = Self::create_field(renderer, [width, height]);
 
         Self {
             vertices,
@@ -124,7 +126,8 @@ impl SliceViewer {
             view_projection: renderer.get_view_projection(settings),
             model: vecmath_util::mat4_identity(),
             field_image_view,
-            slice_size: [settings.slice_width, settings.slice_height],
+            field,
+            slice_size: [width, height],
         }
     }
 
@@ -147,6 +150,77 @@ impl SliceViewer {
         self.field_image_view.clone()
     }
 
+    // complex pressure (re, im) of each pixel computed with the image
+    pub fn field(&self) -> Arc<CpuAccessibleBuffer<[Vector2]>> {
+        self.field.clone()
+    }
+
+    pub fn slice_size(&self) -> [u32; 2] {
+        self.slice_size
+    }
+
+    // position of p in the slice coordinate: x, y on the slice and z along its normal
+    pub fn to_local(&self, p: Vector3) -> Vector3 {
+        let d = vecmath::vec3_sub(p, vecmath_util::to_vec3(&self.model[3]));
+        [
+            vecmath::vec3_dot(d, vecmath_util::to_vec3(&self.model[0])),
+            vecmath::vec3_dot(d, vecmath_util::to_vec3(&self.model[1])),
+            vecmath::vec3_dot(d, vecmath_util::to_vec3(&self.model[2])),
+        ]
+    }
+
+    // world position where the ray hits the slice, if it does
+    pub fn intersect(
+        &self,
+        settings: &ViewerSettings,
+        origin: Vector3,
+        dir: Vector3,
+    ) -> Option<Vector3> {
+        let normal = vecmath_util::to_vec3(&self.model[2]);
+        let denom = vecmath::vec3_dot(dir, normal);
+        if denom.abs() < 1e-6 {
+            return None;
+        }
+        let center = vecmath_util::to_vec3(&self.model[3]);
+        let t = vecmath::vec3_dot(vecmath::vec3_sub(center, origin), normal) / denom;
+        if t <= 0.0 {
+            return None;
+        }
+        let hit = vecmath::vec3_add(origin, vecmath::vec3_scale(dir, t));
+        self.pixel(settings, hit).map(|_| hit)
+    }
+
+    // pixel of the field buffer nearest to p, if p lies on the slice
+    pub fn pixel(&self, settings: &ViewerSettings, p: Vector3) -> Option<[u32; 2]> {
+        let pixel_size = settings.slice_pixel_size as f32;
+        let local = self.to_local(p);
+        if local[2].abs() > pixel_size / 2.0 {
+            return None;
+        }
+        let x = (local[0] / pixel_size + self.slice_size[0] as f32 / 2.0).round();
+        let y = (local[1] / pixel_size + self.slice_size[1] as f32 / 2.0).round();
+        if x < 0.0 || y < 0.0 || x >= self.slice_size[0] as f32 || y >= self.slice_size[1] as f32 {
+            return None;
+        }
+        Some([x as u32, y as u32])
+    }
+
+    // copy of the last computed field, row by row from the bottom; None while the GPU is writing it
+    pub fn read_field(&self) -> Option<Vec<Vector2>> {
+        let field = self.field.read().ok()?;
+        let len = self.slice_size[0] as usize * self.slice_size[1] as usize;
+        Some(field[..len.min(field.len())].to_vec())
+    }
+
+    // complex pressure at p from the last computed field
+    pub fn field_at(&self, settings: &ViewerSettings, p: Vector3) -> Option<Vector2> {
+        let [x, y] = self.pixel(settings, p)?;
+        let field = self.field.read().ok()?;
+        field
+            .get(x as usize + self.slice_size[0] as usize * y as usize)
+            .copied()
+    }
+
     pub fn update(
         &mut self,
         renderer: &mut Renderer,
@@ -162,6 +236,7 @@ impl SliceViewer {
                 settings.slice_height / settings.slice_pixel_size,
             ];
             self.field_image_view = Self::create_field_image_view(renderer, self.slice_size);
+            self.field = Self::create_field(renderer, self.slice_size);
         }
 
         if update_flag.contains(UpdateFlag::UPDATE_CAMERA_POS) {
@@ -242,6 +317,23 @@ impl SliceViewer {
         .unwrap()
     }
 
+    fn create_field(
+        renderer: &Renderer,
+        view_size: [u32; 2],
+    ) -> Arc<CpuAccessibleBuffer<[Vector2]>> {
+        let data_iter = vec![[0., 0.]; view_size[0] as usize * view_size[1] as usize];
+        CpuAccessibleBuffer::from_iter(
+            renderer.device(),
+            BufferUsage {
+                storage_buffer: true,
+                ..BufferUsage::none()
+            },
+            false,
+            data_iter,
+        )
+        .unwrap()
+    }
+
     fn create_vertices(
         device: Arc<Device>,
         settings: &ViewerSettings,
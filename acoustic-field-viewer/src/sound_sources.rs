@@ -4,8 +4,8 @@
  * Created Date: 29/11/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 09/05/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
@@ -15,7 +15,7 @@ use std::f32::consts::PI;
 
 use bytemuck::{Pod, Zeroable};
 
-use crate::{Vector3, Vector4};
+use crate::{Vector2, Vector3, Vector4};
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, Zeroable, Pod)]
@@ -45,6 +45,7 @@ impl Drive {
     }
 }
 
+#[derive(Clone)]
 pub struct SoundSources {
     pos: Vec<Vector4>,
     dir: Vec<Vector3>,
@@ -112,6 +113,19 @@ impl SoundSources {
     ) -> impl ExactSizeIterator<Item = (&Vector4, &mut Drive)> {
         self.pos.iter().zip(self.drive.iter_mut())
     }
+
+    // complex pressure at p with the same model as the compute shader
+    pub fn pressure_at(&self, p: Vector3) -> Vector2 {
+        self.pos
+            .iter()
+            .zip(self.drive.iter())
+            .fold([0.0, 0.0], |[re, im], (tp, drive)| {
+                let r = vecmath::vec3_len([p[0] - tp[0], p[1] - tp[1], p[2] - tp[2]]);
+                let phase = -drive.phase - drive.wave_num * r;
+                let amp = drive.enable * drive.amp / r;
+                [re + amp * phase.cos(), im + amp * phase.sin()]
+            })
+    }
 }
 
 impl Default for SoundSources {
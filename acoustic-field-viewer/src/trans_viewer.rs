@@ -4,8 +4,8 @@
  * Created Date: 30/11/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 09/05/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
@@ -42,7 +42,7 @@ use crate::{
     sound_sources::SoundSources,
     update_flag::UpdateFlag,
     viewer_settings::ViewerSettings,
-    Matrix4, Vector4,
+    Matrix4, Vector3, Vector4,
 };
 
 pub type FieldImageView = Arc<ImageView<Arc<StorageImage>>>;
@@ -221,6 +221,33 @@ impl TransViewer {
         }
     }
 
+    // returns the index of the nearest visible transducer disc hit by the ray
+    pub fn pick(
+        sources: &SoundSources,
+        settings: &ViewerSettings,
+        origin: Vector3,
+        dir: Vector3,
+    ) -> Option<usize> {
+        let radius = 0.5 * settings.source_size;
+        sources
+            .position_dirs()
+            .zip(sources.visibilities())
+            .enumerate()
+            .filter(|(_, (_, &v))| v > 0.0)
+            .filter_map(|(i, ((pos, normal), _))| {
+                let denom = vecmath::vec3_dot(dir, *normal);
+                if denom.abs() < 1e-6 {
+                    return None;
+                }
+                let p = vecmath_util::to_vec3(pos);
+                let t = vecmath::vec3_dot(vecmath::vec3_sub(p, origin), *normal) / denom;
+                let hit = vecmath::vec3_add(origin, vecmath::vec3_scale(dir, t));
+                (t > 0.0 && vecmath_util::dist(hit, p) <= radius).then_some((i, t))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
     fn create_model_instance_data(
         device: Arc<Device>,
         settings: &ViewerSettings,
@@ -4,8 +4,8 @@
  * Created Date: 26/11/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 09/05/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
@@ -24,5 +24,6 @@ bitflags::bitflags! {
         const INIT_AXIS = 1 << 10;
         const UPDATE_AXIS_SIZE = 1 << 11;
         const UPDATE_AXIS_FLAG = 1 << 12;
+        const UPDATE_VOLUME = 1 << 13;
     }
 }
@@ -4,8 +4,8 @@
  * Created Date: 26/11/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 17/05/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
@@ -13,11 +13,15 @@
 
 use std::f32::consts::PI;
 
-use crate::{Vector3, Vector4};
+use crate::{
+    common::color_map::ColorMapKind, field_compute_pipeline::SliceQuantity, Vector3, Vector4,
+};
 use autd3_core::TRANS_SPACING_MM;
 use serde::{Deserialize, Serialize};
 
+// fields missing in older settings take their default values
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
 pub struct ViewerSettings {
     pub source_size: f32,
     pub frequency: f32,
@@ -25,11 +29,15 @@ pub struct ViewerSettings {
     pub axis_length: f32,
     pub axis_width: f32,
     pub color_scale: f32,
+    pub color_map: ColorMapKind,
+    pub db_mode: bool,
+    pub db_range: f32,
     pub slice_alpha: f32,
     pub source_alpha: f32,
     pub slice_width: u32,
     pub slice_height: u32,
     pub slice_pixel_size: u32,
+    pub slice_quantity: SliceQuantity,
     pub slice_pos: Vector4,
     pub slice_angle: Vector3,
     pub camera_pos: Vector3,
@@ -53,6 +61,9 @@ impl Default for ViewerSettings {
         ViewerSettings {
             source_size: autd3_core::TRANS_SPACING_MM as _,
             color_scale: 2.0,
+            color_map: ColorMapKind::Inferno,
+            db_mode: false,
+            db_range: 40.0,
             slice_alpha: 0.95,
             axis_length: 50.0,
             axis_width: 2.0,
@@ -61,6 +72,7 @@ impl Default for ViewerSettings {
             slice_width: 400,
             slice_height: 300,
             slice_pixel_size: 1,
+            slice_quantity: SliceQuantity::Amplitude,
             source_alpha: 1.0,
             slice_pos: [
                 TRANS_SPACING_MM as f32 * 8.5,
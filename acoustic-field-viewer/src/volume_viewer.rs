@@ -0,0 +1,461 @@
+/*
+ * File: volume_viewer.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+use vulkano::{
+    buffer::{BufferUsage, CpuAccessibleBuffer, CpuBufferPool, TypedBufferAccess},
+    command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer},
+    descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet},
+    device::Device,
+    memory::DeviceMemoryAllocationError,
+    pipeline::{
+        graphics::{
+            color_blend::ColorBlendState, depth_stencil::DepthStencilState,
+            input_assembly::InputAssemblyState, vertex_input::BuffersDefinition,
+            viewport::ViewportState,
+        },
+        GraphicsPipeline, Pipeline, PipelineBindPoint,
+    },
+    render_pass::Subpass,
+};
+
+use crate::{
+    renderer::Renderer, update_flag::UpdateFlag, viewer_settings::ViewerSettings, Matrix4, Vector2,
+    Vector3, Vector4,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeRendering {
+    RayMarching,
+    Isosurface,
+}
+
+impl VolumeRendering {
+    pub const ALL: [VolumeRendering; 2] =
+        [VolumeRendering::RayMarching, VolumeRendering::Isosurface];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VolumeRendering::RayMarching => "ray marching",
+            VolumeRendering::Isosurface => "isosurface",
+        }
+    }
+}
+
+pub const MAX_ISO_LEVELS: usize = 4;
+// about 100 MB for the color and field buffers
+pub const MAX_VOXELS: usize = 1 << 22;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct VolumeSettings {
+    pub enable: bool,
+    pub center: Vector3,
+    pub size: Vector3,
+    pub voxel_size: u32,
+    pub rendering: VolumeRendering,
+    // absorption per mm at the color scale for ray marching, alpha of each surface for isosurfaces
+    pub opacity: f32,
+    // amplitudes relative to the color scale
+    pub iso_levels: [f32; MAX_ISO_LEVELS],
+    pub iso_level_num: u32,
+}
+
+impl VolumeSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // voxel_size, coarsened so that the volume has at most MAX_VOXELS voxels
+    pub fn effective_voxel_size(&self) -> u32 {
+        let count = |s: u32| {
+            self.size
+                .iter()
+                .map(|&l| (l / s as f32).max(1.0) as usize)
+                .product::<usize>()
+        };
+        let volume: f32 = self.size.iter().map(|l| l.max(1.0)).product();
+        let mut s = self
+            .voxel_size
+            .max(1)
+            .max((volume / MAX_VOXELS as f32).cbrt() as u32);
+        while count(s) > MAX_VOXELS {
+            s += 1;
+        }
+        s
+    }
+
+    // number of voxels along each axis
+    pub fn dims(&self) -> [u32; 3] {
+        let voxel_size = self.effective_voxel_size() as f32;
+        [
+            (self.size[0] / voxel_size).max(1.0) as u32,
+            (self.size[1] / voxel_size).max(1.0) as u32,
+            (self.size[2] / voxel_size).max(1.0) as u32,
+        ]
+    }
+
+    // the position of voxel (i, j, k), following the grid of FieldComputePipeline::compute_grid
+    pub fn position(&self, i: u32, j: u32, k: u32) -> Vector3 {
+        let [w, h, d] = self.dims();
+        let voxel_size = self.effective_voxel_size() as f32;
+        [
+            self.center[0] + (i as f32 - w as f32 / 2.0) * voxel_size,
+            self.center[1] + (j as f32 - h as f32 / 2.0) * voxel_size,
+            self.center[2] + (k as f32 - (d / 2) as f32) * voxel_size,
+        ]
+    }
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            center: [
+                autd3_core::TRANS_SPACING_MM as f32 * 8.5,
+                autd3_core::TRANS_SPACING_MM as f32 * 6.5,
+                150.,
+            ],
+            size: [100., 100., 100.],
+            voxel_size: 2,
+            rendering: VolumeRendering::RayMarching,
+            opacity: 0.05,
+            iso_levels: [0.5, 0.25, 0.75, 1.0],
+            iso_level_num: 2,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod)]
+struct Vertex {
+    position: [f32; 3],
+}
+vulkano::impl_vertex!(Vertex, position);
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod)]
+struct Data {
+    view: Matrix4,
+    proj: Matrix4,
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod)]
+struct Config {
+    camera_pos: Vector4,
+    box_min: Vector4,
+    box_max: Vector4,
+    iso_levels: [f32; MAX_ISO_LEVELS],
+    width: u32,
+    height: u32,
+    depth: u32,
+    mode: u32,
+    voxel_size: f32,
+    color_scale: f32,
+    opacity: f32,
+    iso_level_num: u32,
+}
+
+#[allow(clippy::needless_question_mark)]
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "../assets/shaders/volume.vert"
+    }
+}
+
+#[allow(clippy::needless_question_mark)]
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "../assets/shaders/volume.frag"
+    }
+}
+
+// colors and complex pressure of each voxel
+type VolumeBuffers = (
+    Arc<CpuAccessibleBuffer<[Vector4]>>,
+    Arc<CpuAccessibleBuffer<[Vector2]>>,
+);
+
+pub struct VolumeViewer {
+    vertices: Arc<CpuAccessibleBuffer<[Vertex]>>,
+    indices: Arc<CpuAccessibleBuffer<[u32]>>,
+    device: Arc<Device>,
+    pipeline: Arc<GraphicsPipeline>,
+    view_projection: (Matrix4, Matrix4),
+    volume: VolumeSettings,
+    color_scale: f32,
+    colors: Arc<CpuAccessibleBuffer<[Vector4]>>,
+    field: Arc<CpuAccessibleBuffer<[Vector2]>>,
+}
+
+impl VolumeViewer {
+    pub fn new(renderer: &Renderer, settings: &ViewerSettings, volume: &VolumeSettings) -> Self {
+        let device = renderer.device();
+        let indices = Self::create_indices(device.clone());
+
+        let vs = vs::load(device.clone()).unwrap();
+        let fs = fs::load(device.clone()).unwrap();
+
+        let subpass = Subpass::from(renderer.render_pass(), 0).unwrap();
+        let pipeline = GraphicsPipeline::start()
+            .vertex_input_state(BuffersDefinition::new().vertex::<Vertex>())
+            .vertex_shader(vs.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .fragment_shader(fs.entry_point("main").unwrap(), ())
+            .color_blend_state(ColorBlendState::new(subpass.num_color_attachments()).blend_alpha())
+            .depth_stencil_state(DepthStencilState::simple_depth_test())
+            .render_pass(subpass)
+            .build(device.clone())
+            .unwrap();
+
+        // a single voxel until the volume is changed, if the requested one cannot be allocated
+        let (volume, (colors, field)) = match Self::create_buffers(device.clone(), volume) {
+            Ok(buffers) => (*volume, buffers),
+            Err(_) => {
+                let volume = VolumeSettings {
+                    size: [1.0; 3],
+                    voxel_size: 1,
+                    ..*volume
+                };
+                (
+                    volume,
+                    Self::create_buffers(device.clone(), &volume).unwrap(),
+                )
+            }
+        };
+        Self {
+            vertices: Self::create_vertices(device.clone(), &volume),
+            indices,
+            colors,
+            field,
+            device,
+            pipeline,
+            view_projection: renderer.get_view_projection(settings),
+            volume,
+            color_scale: settings.color_scale,
+        }
+    }
+
+    // the model matrix to compute the grid of the volume with
+    pub fn model(&self) -> Matrix4 {
+        vecmath_util::mat4_t(self.volume.center)
+    }
+
+    pub fn volume(&self) -> &VolumeSettings {
+        &self.volume
+    }
+
+    pub fn colors(&self) -> Arc<CpuAccessibleBuffer<[Vector4]>> {
+        self.colors.clone()
+    }
+
+    // complex pressure (re, im) of each voxel, x first and z last
+    pub fn field(&self) -> Arc<CpuAccessibleBuffer<[Vector2]>> {
+        self.field.clone()
+    }
+
+    // copy of the last computed field; None while the GPU is writing it
+    pub fn read_field(&self) -> Option<Vec<Vector2>> {
+        let field = self.field.read().ok()?;
+        Some(field[..Self::len(&self.volume).min(field.len())].to_vec())
+    }
+
+    pub fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        view_projection: &(Matrix4, Matrix4),
+        settings: &ViewerSettings,
+        volume: &VolumeSettings,
+        update_flag: UpdateFlag,
+    ) -> Result<(), DeviceMemoryAllocationError> {
+        // the previous grid is kept if the new one cannot be allocated
+        let mut result = Ok(());
+        if update_flag.contains(UpdateFlag::UPDATE_VOLUME) {
+            if Self::len(volume) != Self::len(&self.volume) {
+                result = Self::create_buffers(renderer.device(), volume).map(|(colors, field)| {
+                    self.colors = colors;
+                    self.field = field;
+                });
+            }
+            if result.is_ok() {
+                self.vertices = Self::create_vertices(renderer.device(), volume);
+                self.volume = *volume;
+            }
+        }
+        // the rendering parameters take effect without recomputing the field
+        self.volume = VolumeSettings {
+            center: self.volume.center,
+            size: self.volume.size,
+            voxel_size: self.volume.voxel_size,
+            ..*volume
+        };
+
+        if update_flag.contains(UpdateFlag::UPDATE_COLOR_MAP) {
+            self.color_scale = settings.color_scale;
+        }
+
+        if update_flag.contains(UpdateFlag::UPDATE_CAMERA_POS) {
+            self.view_projection = *view_projection;
+        }
+        result
+    }
+
+    pub fn render(&mut self, builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) {
+        if !self.volume.enable {
+            return;
+        }
+        let desc_set = self.create_descriptor_set();
+        builder
+            .bind_pipeline_graphics(self.pipeline.clone())
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                self.pipeline.layout().clone(),
+                0,
+                desc_set,
+            )
+            .bind_vertex_buffers(0, self.vertices.clone())
+            .bind_index_buffer(self.indices.clone())
+            .draw_indexed(self.indices.len() as u32, 1, 0, 0, 0)
+            .unwrap();
+    }
+
+    fn len(volume: &VolumeSettings) -> usize {
+        volume.dims().iter().map(|&d| d as usize).product()
+    }
+
+    fn create_descriptor_set(&mut self) -> Arc<PersistentDescriptorSet> {
+        let layout = self.pipeline.layout().set_layouts().get(0).unwrap();
+        let view_proj_buf = CpuBufferPool::<Data>::new(self.device.clone(), BufferUsage::all());
+        let uniform_buffer_subbuffer = {
+            let uniform_data = Data {
+                view: self.view_projection.0,
+                proj: self.view_projection.1,
+            };
+            view_proj_buf.next(uniform_data).unwrap()
+        };
+        let write_desc_uni = WriteDescriptorSet::buffer(0, uniform_buffer_subbuffer);
+
+        let config_buf = CpuBufferPool::<Config>::new(self.device.clone(), BufferUsage::all());
+        let uniform_buffer_subbuffer = {
+            let [width, height, depth] = self.volume.dims();
+            let camera = vecmath::mat4_inv(self.view_projection.0)[3];
+            let box_min = self.volume.position(0, 0, 0);
+            let box_max = self.volume.position(width - 1, height - 1, depth - 1);
+            let uniform_data = Config {
+                camera_pos: camera,
+                box_min: vecmath_util::to_vec4(box_min),
+                box_max: vecmath_util::to_vec4(box_max),
+                iso_levels: self.volume.iso_levels,
+                width,
+                height,
+                depth,
+                mode: self.volume.rendering as u32,
+                voxel_size: self.volume.effective_voxel_size() as f32,
+                color_scale: self.color_scale,
+                opacity: self.volume.opacity,
+                iso_level_num: self.volume.iso_level_num.min(MAX_ISO_LEVELS as u32),
+            };
+            config_buf.next(uniform_data).unwrap()
+        };
+        let write_desc_conf = WriteDescriptorSet::buffer(1, uniform_buffer_subbuffer);
+
+        PersistentDescriptorSet::new(
+            layout.clone(),
+            [
+                write_desc_uni,
+                write_desc_conf,
+                WriteDescriptorSet::buffer(2, self.colors.clone()),
+                WriteDescriptorSet::buffer(3, self.field.clone()),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn create_buffers(
+        device: Arc<Device>,
+        volume: &VolumeSettings,
+    ) -> Result<VolumeBuffers, DeviceMemoryAllocationError> {
+        let len = Self::len(volume);
+        Ok((
+            Self::create_buffer(device.clone(), len, [0.; 4])?,
+            Self::create_buffer(device, len, [0.; 2])?,
+        ))
+    }
+
+    fn create_buffer<T>(
+        device: Arc<Device>,
+        len: usize,
+        init: T,
+    ) -> Result<Arc<CpuAccessibleBuffer<[T]>>, DeviceMemoryAllocationError>
+    where
+        T: Pod + Send + Sync,
+    {
+        CpuAccessibleBuffer::from_iter(
+            device,
+            BufferUsage {
+                storage_buffer: true,
+                ..BufferUsage::none()
+            },
+            false,
+            vec![init; len],
+        )
+    }
+
+    // the box enclosing the voxel centers
+    fn create_vertices(
+        device: Arc<Device>,
+        volume: &VolumeSettings,
+    ) -> Arc<CpuAccessibleBuffer<[Vertex]>> {
+        let [w, h, d] = volume.dims();
+        let min = volume.position(0, 0, 0);
+        let max = volume.position(w - 1, h - 1, d - 1);
+        CpuAccessibleBuffer::from_iter(
+            device,
+            BufferUsage::all(),
+            false,
+            (0..8).map(|i| Vertex {
+                position: [
+                    if i & 1 == 0 { min[0] } else { max[0] },
+                    if i & 2 == 0 { min[1] } else { max[1] },
+                    if i & 4 == 0 { min[2] } else { max[2] },
+                ],
+            }),
+        )
+        .unwrap()
+    }
+
+    fn create_indices(device: Arc<Device>) -> Arc<CpuAccessibleBuffer<[u32]>> {
+        let indices: Vec<u32> = vec![
+            0, 2, 1, 1, 2, 3, // -z
+            4, 5, 6, 5, 7, 6, // +z
+            0, 1, 4, 1, 5, 4, // -y
+            2, 6, 3, 3, 6, 7, // +y
+            0, 4, 2, 2, 4, 6, // -x
+            1, 3, 5, 3, 7, 5, // +x
+        ];
+        CpuAccessibleBuffer::<[u32]>::from_iter(
+            device,
+            BufferUsage::index_buffer(),
+            false,
+            indices.into_iter(),
+        )
+        .unwrap()
+    }
+}
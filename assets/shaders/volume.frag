@@ -0,0 +1,121 @@
+/*
+ * File: volume.frag
+ * Project: shaders
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ * 
+ */
+
+#version 450
+
+layout(location = 0) in vec3 v_world_pos;
+
+layout(location = 0) out vec4 f_color;
+
+layout(set = 0, binding = 1) uniform Config {
+    vec4 camera_pos;
+    // centers of the first and last voxels
+    vec4 box_min;
+    vec4 box_max;
+    vec4 iso_levels;
+    uint width;
+    uint height;
+    uint depth;
+    uint mode;
+    float voxel_size;
+    float color_scale;
+    float opacity;
+    uint iso_level_num;
+} config;
+layout(set = 0, binding = 2) readonly buffer Colors {
+    vec4 colors[];
+} colors;
+layout(set = 0, binding = 3) readonly buffer Field {
+    vec2 field[];
+} field;
+
+const int MAX_STEPS = 4096;
+
+uint index(ivec3 g)
+{
+  ivec3 c = clamp(g, ivec3(0), ivec3(config.width - 1, config.height - 1, config.depth - 1));
+  return uint(c.x) + config.width * (uint(c.y) + config.height * uint(c.z));
+}
+
+vec3 to_grid(vec3 p)
+{
+  return (p - config.box_min.xyz) / config.voxel_size;
+}
+
+// trilinear interpolation of the amplitude normalized by color_scale
+float amplitude(vec3 g)
+{
+  vec3 f = fract(g);
+  ivec3 i = ivec3(floor(g));
+  float v = 0.0;
+  for(int k = 0; k < 8; k++) {
+    ivec3 o = ivec3(k & 1, (k >> 1) & 1, (k >> 2) & 1);
+    vec3 w = mix(1.0 - f, f, vec3(o));
+    v += w.x * w.y * w.z * length(field.field[index(i + o)]);
+  }
+  return v / config.color_scale;
+}
+
+vec3 color(vec3 g)
+{
+  return colors.colors[index(ivec3(round(g)))].rgb;
+}
+
+vec3 normal(vec3 g)
+{
+  vec3 n = vec3(amplitude(g + vec3(1, 0, 0)) - amplitude(g - vec3(1, 0, 0)),
+                amplitude(g + vec3(0, 1, 0)) - amplitude(g - vec3(0, 1, 0)),
+                amplitude(g + vec3(0, 0, 1)) - amplitude(g - vec3(0, 0, 1)));
+  return length(n) > 0.0 ? normalize(n) : vec3(0.0);
+}
+
+void main() {
+  vec3 origin = config.camera_pos.xyz;
+  vec3 dir = normalize(v_world_pos - origin);
+  vec3 t0 = (config.box_min.xyz - origin) / dir;
+  vec3 t1 = (config.box_max.xyz - origin) / dir;
+  vec3 t_min = min(t0, t1);
+  vec3 t_max = max(t0, t1);
+  float t_near = max(max(max(t_min.x, t_min.y), t_min.z), 0.0);
+  float t_far = min(min(t_max.x, t_max.y), t_max.z);
+  // march once per pixel from the back faces of the box only
+  if(t_far <= t_near || distance(v_world_pos, origin) < t_far - 1e-3 * max(t_far, 1.0)) discard;
+
+  float dt = 0.5 * config.voxel_size;
+  vec4 acc = vec4(0.0);
+  float prev = amplitude(to_grid(origin + dir * t_near));
+  for(int s = 1; s < MAX_STEPS; s++) {
+    float t = t_near + dt * s;
+    if(t > t_far || acc.a > 0.99) break;
+    vec3 g = to_grid(origin + dir * t);
+    float v = amplitude(g);
+    if(config.mode == 0) {
+      // emission-absorption with the opacity per mm at the color scale
+      float a = 1.0 - exp(-clamp(v, 0.0, 1.0) * config.opacity * dt);
+      acc.rgb += (1.0 - acc.a) * a * color(g);
+      acc.a += (1.0 - acc.a) * a;
+    } else {
+      for(uint l = 0; l < config.iso_level_num; l++) {
+        float level = config.iso_levels[l];
+        if((prev - level) * (v - level) > 0.0 || prev == v) continue;
+        float shade = 0.3 + 0.7 * abs(dot(normal(g), dir));
+        float a = clamp(config.opacity, 0.0, 1.0);
+        acc.rgb += (1.0 - acc.a) * a * shade * color(g);
+        acc.a += (1.0 - acc.a) * a;
+      }
+      prev = v;
+    }
+  }
+  if(acc.a <= 0.0) discard;
+  f_color = vec4(acc.rgb / acc.a, acc.a);
+}
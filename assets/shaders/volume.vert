@@ -0,0 +1,28 @@
+/*
+ * File: volume.vert
+ * Project: shaders
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ * 
+ */
+
+#version 450
+
+layout(location = 0) in vec3 position;
+
+layout(location = 0) out vec3 o_world_pos;
+
+layout(set = 0, binding = 0) uniform Data {
+    mat4 view;
+    mat4 proj;
+} u_view_proj;
+
+void main() {
+    gl_Position = u_view_proj.proj * u_view_proj.view * vec4(position, 1.0);
+    o_world_pos = position;
+}
@@ -0,0 +1,63 @@
+/*
+ * File: breakpoint.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use autd3_core::CPUControlFlags;
+
+use crate::packet::{EventKind, Packet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    Kind(EventKind),
+    CpuFlag(CPUControlFlags),
+    DeviceCountChange,
+}
+
+impl Condition {
+    pub fn matches(&self, packet: &Packet, dev_num: usize) -> bool {
+        match self {
+            Condition::Kind(kind) => packet.kind() == Some(*kind),
+            Condition::CpuFlag(flag) => {
+                packet.kind() == Some(EventKind::Normal)
+                    && packet.cpu_flag().map_or(false, |f| f.contains(*flag))
+            }
+            // geometry datagrams carry one body per device
+            Condition::DeviceCountChange => {
+                packet.kind() == Some(EventKind::Geometries) && packet.num_bodies() != dev_num
+            }
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Condition::Kind(kind) => format!("message is {}", kind.name()),
+            Condition::CpuFlag(flag) => format!("cpu flag contains {:?}", flag),
+            Condition::DeviceCountChange => "device count changes".to_owned(),
+        }
+    }
+}
+
+pub struct Breakpoint {
+    pub condition: Condition,
+    pub enabled: bool,
+    pub hits: u64,
+}
+
+impl Breakpoint {
+    pub fn new(condition: Condition) -> Self {
+        Self {
+            condition,
+            enabled: true,
+            hits: 0,
+        }
+    }
+}
@@ -0,0 +1,233 @@
+/*
+ * File: colorbar.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::{common::color_map::Rgb, Vector2};
+use imgui::Ui;
+
+const TICK_NUM: usize = 5;
+
+const BAR_WIDTH: f32 = 16.0;
+const BAR_MARGIN: f32 = 12.0;
+
+// (position in [0, 1], label) of evenly spaced ticks from min to max
+pub fn ticks(min: f32, max: f32) -> Vec<(f32, String)> {
+    let mag = min.abs().max(max.abs());
+    let decimals = if mag > 0.0 {
+        (2 - mag.log10().floor() as i32).clamp(0, 6) as usize
+    } else {
+        2
+    };
+    (0..TICK_NUM)
+        .map(|i| {
+            let t = i as f32 / (TICK_NUM - 1) as f32;
+            (t, format!("{:.*}", decimals, min + (max - min) * t))
+        })
+        .collect()
+}
+
+pub fn draw(ui: &Ui, colors: &[Rgb], ticks: &[(f32, String)], unit: &str) {
+    if colors.is_empty() {
+        return;
+    }
+    let display = ui.io().display_size;
+    let label_width = ticks
+        .iter()
+        .map(|(_, l)| ui.calc_text_size(l)[0])
+        .fold(ui.calc_text_size(unit)[0], f32::max);
+    let height = (display[1] * 0.4).max(100.0);
+    let x = display[0] - BAR_MARGIN - label_width - 8.0 - BAR_WIDTH;
+    let y = (display[1] - height) / 2.0;
+
+    let draw_list = ui.get_background_draw_list();
+    let step = height / colors.len() as f32;
+    for (i, c) in colors.iter().enumerate() {
+        // the top of the bar is the maximum
+        let top = y + height - (i + 1) as f32 * step;
+        draw_list
+            .add_rect(
+                [x, top],
+                [x + BAR_WIDTH, top + step + 1.0],
+                [c[0], c[1], c[2], 1.0],
+            )
+            .filled(true)
+            .build();
+    }
+    draw_list
+        .add_rect([x, y], [x + BAR_WIDTH, y + height], [1.0, 1.0, 1.0, 1.0])
+        .build();
+    let text_height = ui.text_line_height();
+    for (t, label) in ticks {
+        let ty = y + height * (1.0 - t);
+        draw_list
+            .add_line(
+                [x + BAR_WIDTH, ty],
+                [x + BAR_WIDTH + 4.0, ty],
+                [1.0, 1.0, 1.0, 1.0],
+            )
+            .build();
+        draw_list.add_text(
+            [x + BAR_WIDTH + 8.0, ty - text_height / 2.0],
+            [1.0, 1.0, 1.0, 1.0],
+            label,
+        );
+    }
+    draw_list.add_text([x, y - text_height * 1.5], [1.0, 1.0, 1.0, 1.0], unit);
+}
+
+// 3x5 glyphs, the msb of each row is the left column
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [7, 5, 5, 5, 7],
+        '1' => [2, 6, 2, 2, 7],
+        '2' => [7, 1, 7, 4, 7],
+        '3' => [7, 1, 7, 1, 7],
+        '4' => [5, 5, 7, 1, 1],
+        '5' => [7, 4, 7, 1, 7],
+        '6' => [7, 4, 7, 5, 7],
+        '7' => [7, 1, 1, 1, 1],
+        '8' => [7, 5, 7, 5, 7],
+        '9' => [7, 5, 7, 1, 7],
+        '.' => [0, 0, 0, 0, 2],
+        '-' => [0, 0, 7, 0, 0],
+        'P' => [7, 5, 7, 4, 4],
+        'a' => [0, 3, 5, 5, 3],
+        'd' => [1, 1, 7, 5, 7],
+        'B' => [6, 5, 6, 5, 6],
+        'r' => [0, 3, 4, 4, 4],
+        _ => [0; 5],
+    }
+}
+
+const GLYPH_SCALE: usize = 2;
+const IMAGE_PAD: usize = 8;
+const IMAGE_BAR_WIDTH: usize = 16;
+
+// an RGBA image to draw on, the first row is the top
+pub struct Canvas<'a> {
+    pixels: &'a mut [u8],
+    width: usize,
+    height: usize,
+}
+
+impl<'a> Canvas<'a> {
+    pub fn new(pixels: &'a mut [u8], width: usize, height: usize) -> Self {
+        Self {
+            pixels,
+            width,
+            height,
+        }
+    }
+
+    pub fn put(&mut self, x: usize, y: usize, c: [u8; 4]) {
+        if x < self.width && y < self.height {
+            let i = 4 * (x + self.width * y);
+            self.pixels[i..i + 4].copy_from_slice(&c);
+        }
+    }
+
+    pub fn line(&mut self, a: Vector2, b: Vector2, c: [u8; 4]) {
+        let n = (b[0] - a[0]).abs().max((b[1] - a[1]).abs()).ceil().max(1.0) as usize;
+        for k in 0..=n {
+            let t = k as f32 / n as f32;
+            let x = (a[0] + (b[0] - a[0]) * t).round();
+            let y = (a[1] + (b[1] - a[1]) * t).round();
+            if x >= 0.0 && y >= 0.0 {
+                self.put(x as usize, y as usize, c);
+            }
+        }
+    }
+
+    pub fn text(&mut self, x: usize, y: usize, text: &str) {
+        for (k, c) in text.chars().enumerate() {
+            let g = glyph(c);
+            let ox = x + k * 4 * GLYPH_SCALE;
+            for (row, bits) in g.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (4 >> col) == 0 {
+                        continue;
+                    }
+                    for dy in 0..GLYPH_SCALE {
+                        for dx in 0..GLYPH_SCALE {
+                            self.put(
+                                ox + col * GLYPH_SCALE + dx,
+                                y + row * GLYPH_SCALE + dy,
+                                [255, 255, 255, 255],
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// appends a colorbar to the right of an RGBA image whose first row is the top; returns the new image and its width
+pub fn append_to_image(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    colors: &[Rgb],
+    ticks: &[(f32, String)],
+    unit: &str,
+) -> (Vec<u8>, u32) {
+    let (width, height) = (width as usize, height as usize);
+    let label_len = ticks
+        .iter()
+        .map(|(_, l)| l.chars().count())
+        .fold(unit.chars().count(), usize::max);
+    let extra = IMAGE_PAD * 3 + IMAGE_BAR_WIDTH + 4 + label_len * 4 * GLYPH_SCALE;
+    let new_width = width + extra;
+    let mut out = vec![0u8; new_width * height * 4];
+    for (y, row) in pixels.chunks_exact(width * 4).take(height).enumerate() {
+        out[y * new_width * 4..(y * new_width + width) * 4].copy_from_slice(row);
+        for x in width..new_width {
+            out[(y * new_width + x) * 4..(y * new_width + x + 1) * 4]
+                .copy_from_slice(&[40, 40, 40, 255]);
+        }
+    }
+
+    let mut canvas = Canvas::new(&mut out, new_width, height);
+    let glyph_height = 5 * GLYPH_SCALE;
+    let top = IMAGE_PAD * 2 + glyph_height;
+    if colors.is_empty() || height <= top + IMAGE_PAD + 1 {
+        return (out, new_width as u32);
+    }
+    let bar_height = height - top - IMAGE_PAD;
+    let x0 = width + IMAGE_PAD;
+    canvas.text(x0, IMAGE_PAD, unit);
+    for r in 0..bar_height {
+        let t = 1.0 - r as f32 / (bar_height - 1).max(1) as f32;
+        let c = colors[((t * (colors.len() - 1) as f32).round() as usize).min(colors.len() - 1)];
+        let c = [
+            (c[0] * 255.0) as u8,
+            (c[1] * 255.0) as u8,
+            (c[2] * 255.0) as u8,
+            255,
+        ];
+        for x in x0..x0 + IMAGE_BAR_WIDTH {
+            canvas.put(x, top + r, c);
+        }
+    }
+    for (t, label) in ticks {
+        let y = top + ((1.0 - t) * (bar_height - 1) as f32) as usize;
+        for x in x0 + IMAGE_BAR_WIDTH..x0 + IMAGE_BAR_WIDTH + 4 {
+            canvas.put(x, y, [255, 255, 255, 255]);
+        }
+        canvas.text(
+            x0 + IMAGE_BAR_WIDTH + IMAGE_PAD,
+            y.saturating_sub(glyph_height / 2),
+            label,
+        );
+    }
+    (out, new_width as u32)
+}
@@ -0,0 +1,225 @@
+/*
+ * File: consistency.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::fmt;
+
+use acoustic_field_viewer::ViewerSettings;
+use autd3_firmware_emulator::Emulator;
+
+use crate::fpga_state::FpgaState;
+
+const POINT_STM_SIZE_MAX: usize = 65536;
+const GAIN_STM_SIZE_MAX: usize = 1024;
+// In legacy mode, duty and phase are packed into one word, so twice as many patterns fit
+const GAIN_STM_LEGACY_SIZE_MAX: usize = 2048;
+const SOUND_SPEED_TOLERANCE: f32 = 0.01;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub rule: &'static str,
+    pub device: Option<usize>,
+    pub message: String,
+}
+
+impl Warning {
+    fn new(rule: &'static str, device: Option<usize>, message: String) -> Self {
+        Self {
+            rule,
+            device,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.device {
+            Some(dev) => write!(f, "[{}] device {}: {}", self.rule, dev, self.message),
+            None => write!(f, "[{}] {}", self.rule, self.message),
+        }
+    }
+}
+
+pub struct CheckContext<'a> {
+    pub states: &'a [FpgaState],
+    pub modulations: Vec<(Vec<u8>, u32)>,
+    pub settings: &'a ViewerSettings,
+}
+
+impl<'a> CheckContext<'a> {
+    pub fn new(emulator: &Emulator, states: &'a [FpgaState], settings: &'a ViewerSettings) -> Self {
+        Self {
+            states,
+            modulations: emulator
+                .cpus()
+                .iter()
+                .map(|cpu| cpu.fpga().modulation())
+                .collect(),
+            settings,
+        }
+    }
+}
+
+pub type Rule = fn(&CheckContext) -> Vec<Warning>;
+
+pub const RULES: [Rule; 5] = [
+    check_cycles,
+    check_modulation,
+    check_stm_size,
+    check_freq_division,
+    check_sound_speed,
+];
+
+pub fn check(ctx: &CheckContext) -> Vec<Warning> {
+    RULES.iter().flat_map(|rule| rule(ctx)).collect()
+}
+
+fn check_cycles(ctx: &CheckContext) -> Vec<Warning> {
+    let reference = match ctx.states.first() {
+        Some(s) => &s.cycles,
+        None => return vec![],
+    };
+    ctx.states
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, s)| &s.cycles != reference)
+        .map(|(i, s)| {
+            let n = s
+                .cycles
+                .iter()
+                .zip(reference.iter())
+                .filter(|(a, b)| a != b)
+                .count();
+            Warning::new(
+                "cycle",
+                Some(i),
+                format!("{} transducer cycles differ from device 0", n),
+            )
+        })
+        .collect()
+}
+
+fn check_modulation(ctx: &CheckContext) -> Vec<Warning> {
+    let (ref_data, ref_div) = match ctx.modulations.first() {
+        Some(m) => m,
+        None => return vec![],
+    };
+    let mut res = Vec::new();
+    for (i, (data, div)) in ctx.modulations.iter().enumerate().skip(1) {
+        if data.len() != ref_data.len() {
+            res.push(Warning::new(
+                "modulation",
+                Some(i),
+                format!(
+                    "size {} differs from device 0 ({})",
+                    data.len(),
+                    ref_data.len()
+                ),
+            ));
+        } else if data != ref_data {
+            res.push(Warning::new(
+                "modulation",
+                Some(i),
+                "data differs from device 0".to_owned(),
+            ));
+        }
+        if div != ref_div {
+            res.push(Warning::new(
+                "modulation",
+                Some(i),
+                format!(
+                    "frequency division {} differs from device 0 ({})",
+                    div, ref_div
+                ),
+            ));
+        }
+    }
+    res
+}
+
+fn check_stm_size(ctx: &CheckContext) -> Vec<Warning> {
+    ctx.states
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_stm_mode)
+        .filter_map(|(i, s)| {
+            let max = match (s.is_stm_gain_mode, s.is_legacy_mode) {
+                (true, true) => GAIN_STM_LEGACY_SIZE_MAX,
+                (true, false) => GAIN_STM_SIZE_MAX,
+                (false, _) => POINT_STM_SIZE_MAX,
+            };
+            if s.stm_size == 0 || s.stm_size > max {
+                Some(Warning::new(
+                    "stm",
+                    Some(i),
+                    format!("STM size {} is out of range [1, {}]", s.stm_size, max),
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn check_freq_division(ctx: &CheckContext) -> Vec<Warning> {
+    let mut res = Vec::new();
+    for (i, s) in ctx.states.iter().enumerate() {
+        if s.mod_size > 0 && s.mod_freq_div == 0 {
+            res.push(Warning::new(
+                "division",
+                Some(i),
+                "modulation frequency division is zero".to_owned(),
+            ));
+        }
+        if s.is_stm_mode && s.stm_freq_div == 0 {
+            res.push(Warning::new(
+                "division",
+                Some(i),
+                "STM frequency division is zero".to_owned(),
+            ));
+        }
+        if s.silencer_cycle == 0 {
+            res.push(Warning::new(
+                "division",
+                Some(i),
+                "silencer cycle is zero".to_owned(),
+            ));
+        }
+    }
+    res
+}
+
+fn check_sound_speed(ctx: &CheckContext) -> Vec<Warning> {
+    let expected = ctx.settings.sound_speed;
+    ctx.states
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_stm_mode && !s.is_stm_gain_mode)
+        .filter_map(|(i, s)| {
+            let sound_speed = (s.sound_speed * 1000) as f32 / 1024.0;
+            if (sound_speed - expected).abs() > expected * SOUND_SPEED_TOLERANCE {
+                Some(Warning::new(
+                    "sound speed",
+                    Some(i),
+                    format!(
+                        "PointSTM sound speed {:.1} [mm/s] differs from viewer setting {:.1} [mm/s]",
+                        sound_speed, expected
+                    ),
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
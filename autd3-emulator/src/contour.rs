@@ -0,0 +1,195 @@
+/*
+ * File: contour.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::Vector2;
+use serde::{Deserialize, Serialize};
+
+use crate::colorbar::Canvas;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContourMode {
+    // amplitudes, in Pa when the colorbar is in Pa
+    Fixed,
+    // fractions of the slice maximum
+    Relative,
+    // dB relative to the slice maximum
+    #[default]
+    RelativeDb,
+}
+
+impl ContourMode {
+    pub const ALL: [ContourMode; 3] = [
+        ContourMode::Fixed,
+        ContourMode::Relative,
+        ContourMode::RelativeDb,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContourMode::Fixed => "fixed",
+            ContourMode::Relative => "relative to max",
+            ContourMode::RelativeDb => "dB relative to max",
+        }
+    }
+
+    // amplitude of the level; unit is the amplitude of a fixed level of 1
+    pub fn amplitude(&self, level: f32, max: f32, unit: f32) -> f32 {
+        match self {
+            ContourMode::Fixed => level / unit,
+            ContourMode::Relative => level * max,
+            ContourMode::RelativeDb => max * 10f32.powf(level / 20.0),
+        }
+    }
+
+    pub fn label(&self, level: f32, unit: &str) -> String {
+        match self {
+            ContourMode::Fixed if unit.is_empty() => format!("{}", level),
+            ContourMode::Fixed => format!("{} {}", level, unit),
+            ContourMode::Relative => format!("{}", level),
+            ContourMode::RelativeDb => format!("{} dB", level),
+        }
+    }
+
+    pub fn default_levels() -> Vec<f32> {
+        vec![-3.0, -6.0]
+    }
+}
+
+// segments in pixel coordinates of the slice, pixel (i, j) is at (i, j)
+pub struct Contour {
+    pub label: String,
+    pub segments: Vec<[Vector2; 2]>,
+}
+
+impl Contour {
+    // the topmost point of the contour, where its label is put
+    pub fn label_pos(&self) -> Option<Vector2> {
+        self.segments
+            .iter()
+            .flatten()
+            .copied()
+            .max_by(|a, b| a[1].total_cmp(&b[1]))
+    }
+}
+
+// marching squares over the cells between pixel centers
+pub fn trace(values: &[f32], width: usize, height: usize, level: f32) -> Vec<[Vector2; 2]> {
+    let mut segments = vec![];
+    if width < 2 || height < 2 || values.len() < width * height {
+        return segments;
+    }
+    let v = |i: usize, j: usize| values[i + width * j];
+    for j in 0..height - 1 {
+        for i in 0..width - 1 {
+            let corners = [
+                ([i as f32, j as f32], v(i, j)),
+                ([i as f32 + 1.0, j as f32], v(i + 1, j)),
+                ([i as f32 + 1.0, j as f32 + 1.0], v(i + 1, j + 1)),
+                ([i as f32, j as f32 + 1.0], v(i, j + 1)),
+            ];
+            // crossings on the bottom, right, top and left edges, in this order
+            let p: Vec<Vector2> = (0..4)
+                .filter_map(|e| {
+                    let (pa, a) = corners[e];
+                    let (pb, b) = corners[(e + 1) % 4];
+                    if (a >= level) == (b >= level) {
+                        return None;
+                    }
+                    let t = (level - a) / (b - a);
+                    Some([pa[0] + (pb[0] - pa[0]) * t, pa[1] + (pb[1] - pa[1]) * t])
+                })
+                .collect();
+            match p.len() {
+                2 => segments.push([p[0], p[1]]),
+                4 => {
+                    // saddle; the center decides which pair of opposite corners is cut off
+                    let center = corners.iter().map(|(_, v)| v).sum::<f32>() / 4.0;
+                    if (center >= level) == (corners[0].1 >= level) {
+                        segments.push([p[0], p[1]]);
+                        segments.push([p[2], p[3]]);
+                    } else {
+                        segments.push([p[3], p[0]]);
+                        segments.push([p[1], p[2]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    segments
+}
+
+// draws contours on an RGBA image of the slice whose first row is the top
+pub fn draw_on_image(pixels: &mut [u8], width: u32, height: u32, contours: &[Contour]) {
+    let mut canvas = Canvas::new(pixels, width as usize, height as usize);
+    let flip = |p: Vector2| [p[0], (height as f32 - 1.0) - p[1]];
+    for contour in contours {
+        for &[a, b] in &contour.segments {
+            canvas.line(flip(a), flip(b), [255, 255, 255, 255]);
+        }
+        if let Some(p) = contour.label_pos() {
+            let p = flip(p);
+            canvas.text(
+                p[0].max(0.0) as usize + 2,
+                (p[1].max(0.0) as usize).saturating_sub(12),
+                &contour.label,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // corners of the single cell, in the order (0, 0), (1, 0), (0, 1), (1, 1)
+    const SADDLE: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+    #[test]
+    fn saddle_with_high_center() {
+        // the center (0.5) is above the level, so the low corners are cut off
+        let segments = trace(&SADDLE, 2, 2, 0.25);
+        assert_eq!(
+            segments,
+            vec![[[0.75, 0.0], [1.0, 0.25]], [[0.25, 1.0], [0.0, 0.75]]]
+        );
+    }
+
+    #[test]
+    fn saddle_with_low_center() {
+        // the center (0.5) is below the level, so the high corners are cut off
+        let segments = trace(&SADDLE, 2, 2, 0.75);
+        assert_eq!(
+            segments,
+            vec![[[0.0, 0.25], [0.25, 0.0]], [[1.0, 0.75], [0.75, 1.0]]]
+        );
+    }
+
+    #[test]
+    fn closed_contour_around_peak() {
+        let values = [0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+        let segments = trace(&values, 3, 3, 0.5);
+        assert_eq!(segments.len(), 4);
+        // every crossing is half way between the peak and its neighbors
+        for p in segments.iter().flatten() {
+            assert!(((p[0] - 1.0).abs() + (p[1] - 1.0).abs() - 0.5).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn no_contour_outside_range() {
+        assert!(trace(&SADDLE, 2, 2, 2.0).is_empty());
+        assert!(trace(&SADDLE, 1, 4, 0.5).is_empty());
+        assert!(trace(&SADDLE[..3], 2, 2, 0.5).is_empty());
+    }
+}
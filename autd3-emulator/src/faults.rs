@@ -0,0 +1,83 @@
+/*
+ * File: faults.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use autd3_core::CPUControlFlags;
+
+#[derive(Debug, Clone, Default)]
+pub struct DeviceFault {
+    pub offline: bool,
+    pub ignore_drive: bool,
+    pub stale_msg_id: bool,
+    pub dead: BTreeSet<usize>,
+    // transducer index -> (amp, phase) frozen at the time the fault was injected
+    pub stuck: BTreeMap<usize, (f32, f32)>,
+    // rx message (ack, msg_id) the device would report to the client
+    pub rx: [u8; 2],
+}
+
+impl DeviceFault {
+    pub fn is_faulty(&self) -> bool {
+        self.offline
+            || self.ignore_drive
+            || self.stale_msg_id
+            || !self.dead.is_empty()
+            || !self.stuck.is_empty()
+    }
+
+    pub fn accepts(&self, cpu_flag: CPUControlFlags) -> bool {
+        self.reject_reason(cpu_flag).is_none()
+    }
+
+    pub fn reject_reason(&self, cpu_flag: CPUControlFlags) -> Option<&'static str> {
+        if self.offline {
+            Some("offline: datagram dropped")
+        } else if self.ignore_drive && cpu_flag.contains(CPUControlFlags::WRITE_BODY) {
+            Some("drive update ignored")
+        } else {
+            None
+        }
+    }
+
+    // an offline device keeps reporting its last rx message
+    pub fn respond(&mut self, ack: u8, msg_id: u8) {
+        if self.offline {
+            return;
+        }
+        self.rx[0] = ack;
+        if !self.stale_msg_id {
+            self.rx[1] = msg_id;
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let mut items = Vec::new();
+        if self.offline {
+            items.push("offline".to_owned());
+        }
+        if self.ignore_drive {
+            items.push("ignore drive".to_owned());
+        }
+        if self.stale_msg_id {
+            items.push("stale msg_id".to_owned());
+        }
+        if !self.dead.is_empty() {
+            items.push(format!("{} dead", self.dead.len()));
+        }
+        if !self.stuck.is_empty() {
+            items.push(format!("{} stuck", self.stuck.len()));
+        }
+        items.join(", ")
+    }
+}
@@ -0,0 +1,218 @@
+/*
+ * File: field_export.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use acoustic_field_viewer::{Matrix4, Vector2, Vector3};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    Complex,
+    Amplitude,
+    Phase,
+}
+
+impl Quantity {
+    pub const ALL: [Quantity; 3] = [Quantity::Complex, Quantity::Amplitude, Quantity::Phase];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quantity::Complex => "complex",
+            Quantity::Amplitude => "amplitude",
+            Quantity::Phase => "phase",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Metadata {
+    pub quantity: Quantity,
+    pub width: u32,
+    pub height: u32,
+    pub pixel_size: f32,
+    // column-major slice transform; pixel (i, j) is at slice_model * ((i - width / 2) * pixel_size, (j - height / 2) * pixel_size, 0, 1)
+    pub slice_model: Matrix4,
+    pub frequency: f32,
+    pub sound_speed: f32,
+    // multiply amplitudes by this to get Pa
+    pub source_amplitude: f32,
+}
+
+// the field of a slice, row j = 0 is the bottom of the slice
+pub struct SliceField {
+    pub meta: Metadata,
+    pub field: Vec<Vector2>,
+}
+
+impl SliceField {
+    pub fn position(&self, i: u32, j: u32) -> Vector3 {
+        let m = &self.meta;
+        let x = (i as f32 - m.width as f32 / 2.0) * m.pixel_size;
+        let y = (j as f32 - m.height as f32 / 2.0) * m.pixel_size;
+        let p = vecmath::col_mat4_transform(m.slice_model, [x, y, 0.0, 1.0]);
+        [p[0], p[1], p[2]]
+    }
+
+    fn values(&self, v: &Vector2) -> Vec<f32> {
+        match self.meta.quantity {
+            Quantity::Complex => vec![v[0], v[1]],
+            Quantity::Amplitude => vec![v[0].hypot(v[1])],
+            Quantity::Phase => vec![v[1].atan2(v[0])],
+        }
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.field.len() != self.meta.width as usize * self.meta.height as usize {
+            return Err(anyhow!("field size does not match the slice size"));
+        }
+        Ok(())
+    }
+
+    // writes <path>.npy (field), <path>_coords.npy (height x width x 3 positions) and <path>.json (metadata)
+    pub fn save_npy<P: AsRef<Path>>(&self, path: P) -> Result<Vec<PathBuf>> {
+        self.check()?;
+        let path = path.as_ref();
+        let (h, w) = (self.meta.height as usize, self.meta.width as usize);
+        let field_path = path.with_extension("npy");
+        let descr = match self.meta.quantity {
+            Quantity::Complex => "<c8",
+            _ => "<f4",
+        };
+        write_npy(
+            &field_path,
+            descr,
+            &[h, w],
+            self.field.iter().flat_map(|v| self.values(v)),
+        )?;
+
+        let coords_path = with_suffix(path, "_coords.npy");
+        write_npy(
+            &coords_path,
+            "<f4",
+            &[h, w, 3],
+            (0..h as u32).flat_map(|j| (0..w as u32).flat_map(move |i| self.position(i, j))),
+        )?;
+
+        let meta_path = path.with_extension("json");
+        serde_json::to_writer_pretty(BufWriter::new(File::create(&meta_path)?), &self.meta)?;
+        Ok(vec![field_path, coords_path, meta_path])
+    }
+
+    // one row per pixel: i, j, x, y, z and the quantity; metadata in leading '#' lines
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        self.check()?;
+        let path = path.as_ref().with_extension("csv");
+        let mut w = BufWriter::new(File::create(&path)?);
+        for line in serde_json::to_string_pretty(&self.meta)?.lines() {
+            writeln!(w, "# {}", line)?;
+        }
+        let columns = match self.meta.quantity {
+            Quantity::Complex => "re,im",
+            Quantity::Amplitude => "amplitude",
+            Quantity::Phase => "phase",
+        };
+        writeln!(w, "i,j,x,y,z,{}", columns)?;
+        for (k, v) in self.field.iter().enumerate() {
+            let i = (k % self.meta.width as usize) as u32;
+            let j = (k / self.meta.width as usize) as u32;
+            let p = self.position(i, j);
+            let values: Vec<String> = self.values(v).iter().map(|v| v.to_string()).collect();
+            writeln!(
+                w,
+                "{},{},{},{},{},{}",
+                i,
+                j,
+                p[0],
+                p[1],
+                p[2],
+                values.join(",")
+            )?;
+        }
+        w.flush()?;
+        Ok(path)
+    }
+}
+
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}{}", stem, suffix))
+}
+
+// NPY format version 1.0, little endian, C order
+fn write_npy(
+    path: &Path,
+    descr: &str,
+    shape: &[usize],
+    data: impl Iterator<Item = f32>,
+) -> Result<()> {
+    let header = npy_header(descr, shape);
+    let mut w = BufWriter::new(File::create(path)?);
+    w.write_all(b"\x93NUMPY\x01\x00")?;
+    w.write_all(&(header.len() as u16).to_le_bytes())?;
+    w.write_all(header.as_bytes())?;
+    for v in data {
+        w.write_all(&v.to_le_bytes())?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+fn npy_header(descr: &str, shape: &[usize]) -> String {
+    let shape = shape
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({},), }}",
+        descr, shape
+    );
+    // magic (6) + version (2) + header length (2) + header must be a multiple of 64
+    let total = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - total % 64) % 64));
+    header.push('\n');
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npy_header_is_aligned() {
+        for shape in [vec![1], vec![64, 64], vec![100000, 100000, 2]] {
+            for descr in ["<f4", "<c8"] {
+                let header = npy_header(descr, &shape);
+                assert_eq!((10 + header.len()) % 64, 0);
+                assert!(header.ends_with('\n'));
+                assert!(header.len() <= u16::MAX as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn npy_header_content() {
+        let header = npy_header("<f4", &[3, 2]);
+        assert!(header.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (3, 2,), }"));
+        assert!(header.trim_end_matches('\n').trim_end().ends_with('}'));
+    }
+}
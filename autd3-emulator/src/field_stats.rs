@@ -0,0 +1,84 @@
+/*
+ * File: field_stats.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::Vector2;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMode {
+    Fixed,
+    SliceMax,
+    Percentile,
+    SessionMax,
+}
+
+impl ScaleMode {
+    pub const ALL: [ScaleMode; 4] = [
+        ScaleMode::Fixed,
+        ScaleMode::SliceMax,
+        ScaleMode::Percentile,
+        ScaleMode::SessionMax,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScaleMode::Fixed => "fixed",
+            ScaleMode::SliceMax => "max of slice",
+            ScaleMode::Percentile => "percentile",
+            ScaleMode::SessionMax => "max over session",
+        }
+    }
+}
+
+pub struct FieldStats {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    amplitudes: Vec<f32>,
+}
+
+impl FieldStats {
+    pub fn new(field: &[Vector2]) -> Self {
+        let amplitudes: Vec<f32> = field.iter().map(|[re, im]| re.hypot(*im)).collect();
+        let n = amplitudes.len();
+        let (min, max, mean) = if n == 0 {
+            (0.0, 0.0, 0.0)
+        } else {
+            (
+                amplitudes.iter().copied().fold(f32::INFINITY, f32::min),
+                amplitudes.iter().copied().fold(0.0f32, f32::max),
+                amplitudes.iter().sum::<f32>() / n as f32,
+            )
+        };
+        Self {
+            min,
+            max,
+            mean,
+            amplitudes,
+        }
+    }
+
+    pub fn amplitudes(&self) -> &[f32] {
+        &self.amplitudes
+    }
+
+    // p in [0, 100]
+    pub fn percentile(&self, p: f32) -> f32 {
+        if self.amplitudes.is_empty() {
+            return 0.0;
+        }
+        let mut v = self.amplitudes.clone();
+        let k = ((p / 100.0).clamp(0.0, 1.0) * (v.len() - 1) as f32).round() as usize;
+        *v.select_nth_unstable_by(k, |a, b| a.total_cmp(b)).1
+    }
+}
@@ -0,0 +1,135 @@
+/*
+ * File: fpga_state.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use autd3_firmware_emulator::Emulator;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FpgaState {
+    pub is_legacy_mode: bool,
+    pub is_force_fan: bool,
+    pub is_stm_mode: bool,
+    pub is_stm_gain_mode: bool,
+    pub stm_freq_div: u32,
+    pub stm_size: usize,
+    pub sound_speed: u32,
+    pub silencer_cycle: u16,
+    pub silencer_step: u16,
+    pub mod_size: usize,
+    pub mod_freq_div: u32,
+    pub cycles: Vec<u16>,
+}
+
+impl FpgaState {
+    pub const LABELS: [&'static str; 12] = [
+        "Legacy",
+        "Force fan",
+        "STM",
+        "Gain STM",
+        "STM div",
+        "STM size",
+        "Sound speed",
+        "Silencer cycle",
+        "Silencer step",
+        "Mod size",
+        "Mod div",
+        "Cycle",
+    ];
+
+    pub fn collect(emulator: &Emulator) -> Vec<Self> {
+        emulator
+            .cpus()
+            .iter()
+            .map(|cpu| {
+                let fpga = cpu.fpga();
+                let (modulation, mod_freq_div) = fpga.modulation();
+                Self {
+                    is_legacy_mode: fpga.is_legacy_mode(),
+                    is_force_fan: fpga.is_force_fan(),
+                    is_stm_mode: fpga.is_stm_mode(),
+                    is_stm_gain_mode: fpga.is_stm_gain_mode(),
+                    stm_freq_div: fpga.stm_frequency_division(),
+                    stm_size: fpga.drives().len(),
+                    sound_speed: fpga.sound_speed(),
+                    silencer_cycle: fpga.silencer_cycle(),
+                    silencer_step: fpga.silencer_step(),
+                    mod_size: modulation.len(),
+                    mod_freq_div,
+                    cycles: fpga.cycles().to_vec(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn values(&self) -> [String; 12] {
+        let cycles = match (self.cycles.iter().min(), self.cycles.iter().max()) {
+            (Some(min), Some(max)) if min == max => format!("{}", min),
+            (Some(min), Some(max)) => format!("{}-{}", min, max),
+            _ => "-".to_owned(),
+        };
+        [
+            Self::flag(self.is_legacy_mode),
+            Self::flag(self.is_force_fan),
+            Self::flag(self.is_stm_mode),
+            Self::flag(self.is_stm_gain_mode),
+            format!("{}", self.stm_freq_div),
+            format!("{}", self.stm_size),
+            format!("{:.1}", (self.sound_speed * 1000) as f32 / 1024.0),
+            format!("{}", self.silencer_cycle),
+            format!("{}", self.silencer_step),
+            format!("{}", self.mod_size),
+            format!("{}", self.mod_freq_div),
+            cycles,
+        ]
+    }
+
+    fn flag(v: bool) -> String {
+        if v { "ON" } else { "OFF" }.to_owned()
+    }
+
+    fn field_eq(&self, other: &Self, field: usize) -> bool {
+        match field {
+            0 => self.is_legacy_mode == other.is_legacy_mode,
+            1 => self.is_force_fan == other.is_force_fan,
+            2 => self.is_stm_mode == other.is_stm_mode,
+            3 => self.is_stm_gain_mode == other.is_stm_gain_mode,
+            4 => self.stm_freq_div == other.stm_freq_div,
+            5 => self.stm_size == other.stm_size,
+            6 => self.sound_speed == other.sound_speed,
+            7 => self.silencer_cycle == other.silencer_cycle,
+            8 => self.silencer_step == other.silencer_step,
+            9 => self.mod_size == other.mod_size,
+            10 => self.mod_freq_div == other.mod_freq_div,
+            11 => self.cycles == other.cycles,
+            _ => unreachable!(),
+        }
+    }
+}
+
+// A value differs from the rest when it is not in the largest group of equal values.
+// If several groups tie for the largest, every device that disagrees with any other is marked.
+pub fn find_outliers(states: &[FpgaState]) -> Vec<[bool; 12]> {
+    let mut res = vec![[false; 12]; states.len()];
+    for field in 0..FpgaState::LABELS.len() {
+        let counts: Vec<usize> = states
+            .iter()
+            .map(|s| states.iter().filter(|o| s.field_eq(o, field)).count())
+            .collect();
+        let max = counts.iter().copied().max().unwrap_or(0);
+        let tied = counts.iter().filter(|&&c| c == max).count() > max;
+        for (r, &c) in res.iter_mut().zip(counts.iter()) {
+            r[field] = c < states.len() && (c < max || tied);
+        }
+    }
+    res
+}
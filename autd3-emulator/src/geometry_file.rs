@@ -0,0 +1,186 @@
+/*
+ * File: geometry_file.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{ffi::OsStr, fs, path::Path};
+
+use acoustic_field_viewer::Vector3;
+use anyhow::{anyhow, Result};
+use autd3_core::NUM_TRANS_IN_UNIT;
+use serde::{Deserialize, Serialize};
+
+use crate::server::Geometry;
+
+// {
+//   "devices": [
+//     { "origin": [0, 0, 0], "right": [1, 0, 0], "up": [0, 1, 0] },
+//     { "transducers": [ { "pos": [0, 0, 0], "dir": [0, 0, 1] }, ... ] }
+//   ]
+// }
+// or the same in TOML, when the file has the .toml extension
+#[derive(Deserialize)]
+struct GeometryFile {
+    devices: Vec<DeviceDef>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DeviceDef {
+    Autd {
+        origin: Vector3,
+        right: Vector3,
+        up: Vector3,
+    },
+    Transducers {
+        transducers: Vec<TransducerDef>,
+    },
+}
+
+#[derive(Deserialize)]
+struct TransducerDef {
+    pos: Vector3,
+    dir: Vector3,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceLayout {
+    pub axis: Geometry,
+    pub transducers: Vec<(Vector3, Vector3)>,
+}
+
+impl DeviceLayout {
+    pub fn from_geometry(geometry: Geometry) -> Self {
+        let transducers = geometry.make_autd_transducers();
+        Self {
+            axis: geometry,
+            transducers,
+        }
+    }
+
+    // the axis of a free layout is placed at the first transducer, with z along its direction
+    // and x toward the second transducer
+    fn from_transducers(transducers: Vec<TransducerDef>) -> Result<Self> {
+        if transducers.len() != NUM_TRANS_IN_UNIT {
+            return Err(anyhow!(
+                "a device must have {} transducers, but {} are given",
+                NUM_TRANS_IN_UNIT,
+                transducers.len()
+            ));
+        }
+        let origin = transducers[0].pos;
+        let z = vecmath::vec3_normalized(transducers[0].dir);
+        let d = vecmath::vec3_sub(transducers[1].pos, origin);
+        let d = vecmath::vec3_sub(d, vecmath::vec3_scale(z, vecmath::vec3_dot(d, z)));
+        if vecmath::vec3_len(d) < f32::EPSILON {
+            return Err(anyhow!(
+                "the second transducer must not be on the axis of the first one"
+            ));
+        }
+        let right = vecmath::vec3_normalized(d);
+        let up = vecmath::vec3_cross(z, right);
+        Ok(Self {
+            axis: Geometry { origin, right, up },
+            transducers: transducers
+                .into_iter()
+                .map(|t| (t.pos, vecmath::vec3_normalized(t.dir)))
+                .collect(),
+        })
+    }
+}
+
+fn parse(text: &str, is_toml: bool) -> Result<Vec<DeviceLayout>> {
+    let file: GeometryFile = if is_toml {
+        toml::from_str(text)?
+    } else {
+        serde_json::from_str(text)?
+    };
+    file.devices
+        .into_iter()
+        .map(|d| match d {
+            DeviceDef::Autd { origin, right, up } => {
+                Ok(DeviceLayout::from_geometry(Geometry { origin, right, up }))
+            }
+            DeviceDef::Transducers { transducers } => DeviceLayout::from_transducers(transducers),
+        })
+        .collect()
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<DeviceLayout>> {
+    let path = path.as_ref();
+    let is_toml = path.extension() == Some(OsStr::new("toml"));
+    parse(&fs::read_to_string(path)?, is_toml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vector3, b: Vector3) {
+        assert!(
+            vecmath::vec3_len(vecmath::vec3_sub(a, b)) < 1e-4,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    fn transducer_defs(geometry: &Geometry) -> Vec<TransducerDef> {
+        geometry
+            .make_autd_transducers()
+            .into_iter()
+            .map(|(pos, dir)| TransducerDef { pos, dir })
+            .collect()
+    }
+
+    #[test]
+    fn free_layout_axis_follows_transducers() {
+        let s = std::f32::consts::FRAC_1_SQRT_2;
+        for geometry in [
+            Geometry {
+                origin: [0.0, 0.0, 0.0],
+                right: [1.0, 0.0, 0.0],
+                up: [0.0, 1.0, 0.0],
+            },
+            Geometry {
+                origin: [10.0, -20.0, 30.0],
+                right: [0.0, s, s],
+                up: [0.0, -s, s],
+            },
+        ] {
+            let layout = DeviceLayout::from_transducers(transducer_defs(&geometry)).unwrap();
+            assert_close(layout.axis.origin, geometry.origin);
+            assert_close(layout.axis.right, geometry.right);
+            assert_close(layout.axis.up, geometry.up);
+        }
+    }
+
+    #[test]
+    fn parse_toml() {
+        let text = r#"
+            [[devices]]
+            origin = [0.0, 0.0, 0.0]
+            right = [1.0, 0.0, 0.0]
+            up = [0.0, 1.0, 0.0]
+
+            [[devices]]
+            origin = [192.0, 0.0, 0.0]
+            right = [1.0, 0.0, 0.0]
+            up = [0.0, 1.0, 0.0]
+        "#;
+        let layouts = parse(text, true).unwrap();
+        assert_eq!(layouts.len(), 2);
+        assert!(layouts
+            .iter()
+            .all(|l| l.transducers.len() == NUM_TRANS_IN_UNIT));
+        assert_close(layouts[1].axis.origin, [192.0, 0.0, 0.0]);
+    }
+}
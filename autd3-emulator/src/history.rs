@@ -0,0 +1,145 @@
+/*
+ * File: history.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::collections::VecDeque;
+
+use autd3_core::{Duty, Phase, NUM_TRANS_IN_UNIT};
+use autd3_firmware_emulator::Emulator;
+use chrono::{DateTime, Local};
+
+use crate::packet::{EventKind, Packet};
+
+pub type Drives = Vec<Vec<([Duty; NUM_TRANS_IN_UNIT], [Phase; NUM_TRANS_IN_UNIT])>>;
+
+pub struct Snapshot {
+    pub id: u64,
+    pub time: DateTime<Local>,
+    pub msg_id: u8,
+    pub kind: EventKind,
+    pub drives: Drives,
+    pub cycles: Vec<u16>,
+    // one entry per device
+    pub modulations: Vec<(Vec<u8>, u32)>,
+    pub static_mod: f32,
+    pub is_stm_mode: bool,
+}
+
+impl Snapshot {
+    pub fn stm_size(&self) -> usize {
+        self.drives.first().map_or(0, |d| d.len())
+    }
+
+    // approximate heap size, dominated by the drive tables of GainSTM
+    fn size(&self) -> usize {
+        self.drives
+            .iter()
+            .map(|d| std::mem::size_of_val(d.as_slice()))
+            .sum::<usize>()
+            + std::mem::size_of_val(self.cycles.as_slice())
+            + self.modulations.iter().map(|m| m.0.len()).sum::<usize>()
+    }
+}
+
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+    max: usize,
+    max_bytes: usize,
+    bytes: usize,
+    next_id: u64,
+}
+
+impl History {
+    pub fn new(max: usize, max_bytes: usize) -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            max,
+            max_bytes,
+            bytes: 0,
+            next_id: 0,
+        }
+    }
+
+    pub fn capture(&mut self, packet: &Packet, emulator: &Emulator, static_mod: f32) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let msg_id = packet.header().map_or(0, |h| h.msg_id);
+        let snapshot = Snapshot {
+            id,
+            time: packet.time,
+            msg_id,
+            kind: EventKind::from_msg_id(msg_id),
+            drives: emulator
+                .cpus()
+                .iter()
+                .map(|cpu| cpu.fpga().drives())
+                .collect(),
+            cycles: emulator
+                .cpus()
+                .iter()
+                .flat_map(|cpu| cpu.fpga().cycles())
+                .collect(),
+            modulations: emulator
+                .cpus()
+                .iter()
+                .map(|cpu| cpu.fpga().modulation())
+                .collect(),
+            static_mod,
+            is_stm_mode: emulator
+                .cpus()
+                .first()
+                .map_or(false, |cpu| cpu.fpga().is_stm_mode()),
+        };
+        self.bytes += snapshot.size();
+        self.snapshots.push_back(snapshot);
+        self.truncate();
+        id
+    }
+
+    pub fn set_max(&mut self, max: usize, max_bytes: usize) {
+        self.max = max;
+        self.max_bytes = max_bytes;
+        self.truncate();
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.bytes = 0;
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn snapshots(&self) -> &VecDeque<Snapshot> {
+        &self.snapshots
+    }
+
+    pub fn find(&self, id: u64) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|s| s.id == id)
+    }
+
+    pub fn position(&self, id: u64) -> Option<usize> {
+        self.snapshots.iter().position(|s| s.id == id)
+    }
+
+    // the latest snapshot is kept even if it alone exceeds max_bytes
+    fn truncate(&mut self) {
+        while self.snapshots.len() > self.max
+            || (self.bytes > self.max_bytes && self.snapshots.len() > 1)
+        {
+            if let Some(s) = self.snapshots.pop_front() {
+                self.bytes -= s.size();
+            }
+        }
+    }
+}
@@ -4,8 +4,8 @@
  * Created Date: 09/05/2022
  * Author: Shun Suzuki
  * -----
- * Last Modified: 24/08/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2022 Hapis Lab. All rights reserved.
  *
@@ -18,6 +18,8 @@ use std::{
     thread::{self, JoinHandle},
 };
 
+use crate::packet::Packet;
+
 const BUF_SIZE: usize = 65536;
 
 macro_rules! if_not_open_or_cannot_read {
@@ -56,7 +58,7 @@ impl Interface {
         })
     }
 
-    pub fn start(&mut self, tx: Sender<Vec<u8>>) -> Result<()> {
+    pub fn start(&mut self, tx: Sender<Packet>) -> Result<()> {
         let socket = self.socket.try_clone()?;
         write_rwlock!(self.is_open, true);
         let is_open = self.is_open.clone();
@@ -66,7 +68,7 @@ impl Interface {
             match socket.recv_from(&mut buf) {
                 Ok((amt, _src)) => {
                     let rx_buf = &mut buf[..amt];
-                    tx.send(rx_buf.to_vec()).ok();
+                    tx.send(Packet::new(rx_buf.to_vec())).ok();
                 }
                 Err(e) => eprintln!("{}", e),
             }
@@ -0,0 +1,258 @@
+/*
+ * File: logger.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::packet::EventKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub const ALL: [Level; 4] = [Level::Debug, Level::Info, Level::Warn, Level::Error];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+
+    pub fn color(&self) -> [f32; 4] {
+        match self {
+            Level::Debug => [0.6, 0.6, 0.6, 1.0],
+            Level::Info => [1.0, 1.0, 1.0, 1.0],
+            Level::Warn => [1.0, 0.8, 0.2, 1.0],
+            Level::Error => [1.0, 0.3, 0.3, 1.0],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub device: Option<usize>,
+    pub kind: Option<EventKind>,
+    pub message: String,
+}
+
+impl Record {
+    pub fn new<S: Into<String>>(level: Level, message: S) -> Self {
+        Self {
+            time: Local::now(),
+            level,
+            device: None,
+            kind: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn debug<S: Into<String>>(message: S) -> Self {
+        Self::new(Level::Debug, message)
+    }
+
+    pub fn info<S: Into<String>>(message: S) -> Self {
+        Self::new(Level::Info, message)
+    }
+
+    pub fn warn<S: Into<String>>(message: S) -> Self {
+        Self::new(Level::Warn, message)
+    }
+
+    pub fn error<S: Into<String>>(message: S) -> Self {
+        Self::new(Level::Error, message)
+    }
+
+    pub fn device(mut self, device: Option<usize>) -> Self {
+        self.device = device;
+        self
+    }
+
+    pub fn kind(mut self, kind: EventKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        #[derive(Serialize)]
+        struct JsonRecord<'a> {
+            time: String,
+            level: &'static str,
+            device: Option<usize>,
+            kind: Option<&'static str>,
+            message: &'a str,
+        }
+        serde_json::to_string(&JsonRecord {
+            time: self.time.to_rfc3339(),
+            level: self.level.name(),
+            device: self.device,
+            kind: self.kind.map(|k| k.name()),
+            message: &self.message,
+        })
+    }
+
+    pub fn format(&self) -> String {
+        let mut s = format!(
+            "{} [{:<5}]",
+            self.time.format("%Y-%m-%d %H:%M:%S.%3f"),
+            self.level.name()
+        );
+        if let Some(kind) = self.kind {
+            s.push_str(&format!(" ({})", kind.name()));
+        }
+        if let Some(dev) = self.device {
+            s.push_str(&format!(" device {}:", dev));
+        }
+        s.push(' ');
+        s.push_str(&self.message);
+        s
+    }
+}
+
+// JSON-lines file; when it grows beyond max_size, log.jsonl is renamed to log.jsonl.1,
+// log.jsonl.1 to log.jsonl.2, and so on up to max_files
+struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open<P: AsRef<Path>>(path: P, max_size: u64, max_files: usize) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_size,
+            max_files,
+            writer: BufWriter::new(file),
+            size,
+        })
+    }
+
+    fn write(&mut self, line: &str) -> Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 + 1 > self.max_size {
+            self.rotate()?;
+        }
+        writeln!(self.writer, "{}", line)?;
+        self.writer.flush()?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn rotated_path(&self, i: usize) -> PathBuf {
+        let mut p = self.path.clone().into_os_string();
+        p.push(format!(".{}", i));
+        p.into()
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for i in (1..self.max_files).rev() {
+                let from = self.rotated_path(i);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(i + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.writer = BufWriter::new(File::create(&self.path)?);
+        self.size = 0;
+        Ok(())
+    }
+}
+
+pub struct Logger {
+    records: VecDeque<Record>,
+    max: usize,
+    file: Option<RotatingFile>,
+}
+
+impl Logger {
+    pub fn new(max: usize) -> Self {
+        Self {
+            records: VecDeque::new(),
+            max,
+            file: None,
+        }
+    }
+
+    pub fn push(&mut self, record: Record) -> Result<()> {
+        let res = match &mut self.file {
+            Some(file) => record
+                .to_json()
+                .map_err(anyhow::Error::from)
+                .and_then(|json| file.write(&json)),
+            None => Ok(()),
+        };
+        self.records.push_back(record);
+        self.truncate();
+        res
+    }
+
+    pub fn open_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        max_size: u64,
+        max_files: usize,
+    ) -> Result<()> {
+        self.file = Some(RotatingFile::open(path, max_size, max_files)?);
+        Ok(())
+    }
+
+    pub fn close_file(&mut self) {
+        self.file = None;
+    }
+
+    pub fn set_max(&mut self, max: usize) {
+        self.max = max;
+        self.truncate();
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    pub fn records(&self) -> &VecDeque<Record> {
+        &self.records
+    }
+
+    fn truncate(&mut self) {
+        while self.records.len() > self.max {
+            self.records.pop_front();
+        }
+    }
+}
@@ -4,8 +4,8 @@
  * Created Date: 06/07/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 24/08/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
@@ -16,26 +16,54 @@
     windows_subsystem = "windows"
 )]
 
+mod breakpoint;
+mod colorbar;
+mod consistency;
+mod contour;
+mod faults;
+mod field_export;
+mod field_stats;
+mod fpga_state;
+mod geometry_file;
+mod history;
 mod interface;
+mod logger;
+mod modulation;
+mod packet;
+mod playground;
+mod profile;
+mod scene;
 mod server;
 mod settings;
+mod slices;
+mod traffic;
+mod vtk_export;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
-use std::{collections::VecDeque, f32::consts::PI, path::Path, time::Instant};
+use std::{
+    f32::consts::PI,
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+    time::Instant,
+};
 
 use acoustic_field_viewer::{
     camera_helper,
+    common::color_map::{self, ColorMapKind, Rgb},
     dir_viewer::{Axis3D, DirectionViewer},
-    field_compute_pipeline::FieldComputePipeline,
+    field_compute_pipeline::{FieldComputePipeline, SliceQuantity},
+    focus_gizmo::FocusGizmo,
     renderer::Renderer,
     slice_viewer::SliceViewer,
     sound_sources::{Drive, SoundSources},
     trans_viewer::TransViewer,
-    Matrix4, UpdateFlag,
+    volume_viewer::{VolumeRendering, VolumeViewer, MAX_ISO_LEVELS, MAX_VOXELS},
+    Matrix4, UpdateFlag, Vector2, Vector3, ViewerSettings,
 };
 
-use autd3_core::{CPUControlFlags, Duty, Phase, FPGA_CLK_FREQ, NUM_TRANS_IN_UNIT};
+use autd3_core::{CPUControlFlags, FPGA_CLK_FREQ, NUM_TRANS_IN_UNIT};
+use autd3_firmware_emulator::Emulator;
 use imgui::*;
 use imgui_winit_support::{HiDpiMode, WinitPlatform};
 use server::{AUTDEvent, AUTDServer};
@@ -52,7 +80,27 @@ use winit::{
     platform::run_return::EventLoopExtRunReturn,
 };
 
-use crate::settings::Setting;
+use crate::{
+    breakpoint::{Breakpoint, Condition as BreakCondition},
+    consistency::{CheckContext, Warning},
+    contour::{Contour, ContourMode},
+    faults::DeviceFault,
+    field_export::{Metadata, Quantity, SliceField},
+    field_stats::{FieldStats, ScaleMode},
+    fpga_state::{find_outliers, FpgaState},
+    geometry_file::DeviceLayout,
+    history::{Drives, History},
+    logger::{Level, Logger, Record},
+    modulation::ModulationAnalysis,
+    packet::{BodyFormat, EventKind, Packet},
+    playground::{Pattern, Playground},
+    profile::{LineSpace, Profile},
+    scene::{DeviceScene, Scene},
+    settings::Setting,
+    slices::{Slice, SliceSetting},
+};
+
+const COLORBAR_SIZE: usize = 64;
 
 struct App {
     setting: Setting,
@@ -65,7 +113,7 @@ struct App {
     field_compute_pipeline: FieldComputePipeline,
     view_projection: (Matrix4, Matrix4),
     stm_idx: i32,
-    log_buf: VecDeque<String>,
+    logger: Logger,
     last_frame: Instant,
     last_frame_fps: Instant,
     frame_count: usize,
@@ -73,7 +121,9 @@ struct App {
     save_image: bool,
     recording: bool,
     modulation: (Vec<u8>, u32),
-    drives: Vec<Vec<([Duty; NUM_TRANS_IN_UNIT], [Phase; NUM_TRANS_IN_UNIT])>>,
+    // one entry per device; modulation is that of the first device
+    modulations: Vec<(Vec<u8>, u32)>,
+    drives: Drives,
     cycles: Vec<u16>,
     is_legacy_mode: bool,
     is_stm_mode: bool,
@@ -84,6 +134,46 @@ struct App {
     silencer_cycle: u16,
     silencer_step: u16,
     static_mod: f32,
+    fpga_states: Vec<FpgaState>,
+    warnings: Vec<Warning>,
+    mod_analysis: ModulationAnalysis,
+    show_mod_window: bool,
+    mod_zoom: f32,
+    mod_offset: f32,
+    mod_spectrum_max: f32,
+    packet_kind_filter: usize,
+    packet_flag_filter: String,
+    selected_packet: Option<u64>,
+    log_level_filter: usize,
+    log_kind_filter: usize,
+    history: History,
+    history_cursor: Option<u64>,
+    bp_condition: usize,
+    bp_kind: usize,
+    bp_flag_bit: i32,
+    keep_geometry: bool,
+    fault_device: usize,
+    fault_trans: i32,
+    layouts: Vec<DeviceLayout>,
+    playground: Playground,
+    focus_gizmo: FocusGizmo,
+    gizmo_viewer: DirectionViewer,
+    pinned_trans: Option<usize>,
+    slice_markers: Vec<Vector3>,
+    custom_color_map: Vec<Rgb>,
+    colorbar_colors: Vec<Rgb>,
+    field_pending: bool,
+    slice_stats: Option<FieldStats>,
+    session_max: f32,
+    slices: Vec<Slice>,
+    volume_viewer: VolumeViewer,
+    volume_pipeline: FieldComputePipeline,
+    volume_pending: bool,
+    contours: Vec<Contour>,
+    contour_size: [u32; 2],
+    profile: Option<Profile>,
+    profile_pending: bool,
+    vtk_export: Option<JoinHandle<Result<[PathBuf; 2]>>>,
 }
 
 impl App {
@@ -91,9 +181,18 @@ impl App {
         let trans_viewer = TransViewer::new(renderer, &setting.viewer_setting);
         let slice_viewer = SliceViewer::new(renderer, &setting.viewer_setting);
         let dir_viewer = DirectionViewer::new(renderer, &setting.viewer_setting);
+        let gizmo_viewer = DirectionViewer::new(renderer, &setting.viewer_setting);
         let field_compute_pipeline =
             FieldComputePipeline::new(renderer.queue(), &setting.viewer_setting);
+        let volume_viewer = VolumeViewer::new(renderer, &setting.viewer_setting, &setting.volume);
+        let volume_pipeline = FieldComputePipeline::new(renderer.queue(), &setting.viewer_setting);
         let view_projection = renderer.get_view_projection(&setting.viewer_setting);
+        let logger = Logger::new(setting.log_max as _);
+        let history = History::new(
+            setting.history_max as _,
+            setting.history_max_size as usize * 1024 * 1024,
+        );
+        let colorbar_colors = setting.viewer_setting.color_map.sample(&[], COLORBAR_SIZE);
 
         Self {
             setting,
@@ -106,7 +205,7 @@ impl App {
             field_compute_pipeline,
             view_projection,
             stm_idx: 0,
-            log_buf: VecDeque::new(),
+            logger,
             last_frame: std::time::Instant::now(),
             last_frame_fps: std::time::Instant::now(),
             frame_count: 0,
@@ -114,6 +213,7 @@ impl App {
             save_image: false,
             recording: false,
             modulation: (vec![], 0),
+            modulations: vec![],
             drives: vec![],
             cycles: vec![],
             is_legacy_mode: true,
@@ -125,6 +225,46 @@ impl App {
             silencer_cycle: 0,
             silencer_step: 0,
             static_mod: 0.0,
+            fpga_states: vec![],
+            warnings: vec![],
+            mod_analysis: ModulationAnalysis::new(&[], 0),
+            show_mod_window: false,
+            mod_zoom: 1.0,
+            mod_offset: 0.0,
+            mod_spectrum_max: 0.0,
+            packet_kind_filter: 0,
+            packet_flag_filter: String::new(),
+            selected_packet: None,
+            log_level_filter: 0,
+            log_kind_filter: 0,
+            history,
+            history_cursor: None,
+            bp_condition: 0,
+            bp_kind: 0,
+            bp_flag_bit: 0,
+            keep_geometry: true,
+            fault_device: 0,
+            fault_trans: 0,
+            layouts: vec![],
+            playground: Playground::new(),
+            focus_gizmo: FocusGizmo::new(),
+            gizmo_viewer,
+            pinned_trans: None,
+            slice_markers: vec![],
+            custom_color_map: vec![],
+            colorbar_colors,
+            field_pending: false,
+            slice_stats: None,
+            session_max: 0.0,
+            slices: vec![],
+            volume_viewer,
+            volume_pipeline,
+            volume_pending: false,
+            contours: vec![],
+            contour_size: [0, 0],
+            profile: None,
+            profile_pending: true,
+            vtk_export: None,
         }
     }
 
@@ -166,11 +306,19 @@ impl App {
 
         self.trans_viewer.render(&mut builder);
         self.slice_viewer.render(&mut builder);
+        self.slices
+            .iter_mut()
+            .for_each(|slice| slice.render(&mut builder));
         self.dir_viewer.render(&mut builder);
+        self.gizmo_viewer.render(&mut builder);
+        self.volume_viewer.render(&mut builder);
         builder.end_render_pass().unwrap();
         let command_buffer = builder.build().unwrap();
 
         let mut update_flag = self.handle_autd(autd_server);
+        update_flag |= self.update_field_stats();
+        self.poll_vtk_export();
+        update_flag |= self.update_gizmo(imgui.io());
         update_flag |= self.update_camera(renderer, imgui.io());
 
         let io = imgui.io_mut();
@@ -191,8 +339,23 @@ impl App {
         }
 
         let ui = imgui.frame();
-        update_flag |= self.update_ui(&ui, renderer);
+        update_flag |= self.update_ui(&ui, renderer, autd_server);
+        if update_flag.contains(UpdateFlag::UPDATE_SOURCE_DRIVE) {
+            if self.playground.enabled {
+                self.playground.apply(&mut self.sources);
+            }
+            self.apply_transducer_faults(autd_server.faults());
+        }
         self.update_view(renderer, update_flag);
+        self.profile_pending |= update_flag.intersects(
+            UpdateFlag::INIT_SOURCE
+                | UpdateFlag::UPDATE_SLICE_POS
+                | UpdateFlag::UPDATE_SOURCE_DRIVE
+                | UpdateFlag::UPDATE_SOURCE_FLAG,
+        );
+        if self.profile_pending && self.setting.show_profile {
+            self.update_profile();
+        }
 
         let update_field = update_flag.contains(UpdateFlag::INIT_SOURCE)
             || update_flag.contains(UpdateFlag::UPDATE_COLOR_MAP)
@@ -200,28 +363,49 @@ impl App {
             || update_flag.contains(UpdateFlag::UPDATE_SLICE_SIZE)
             || update_flag.contains(UpdateFlag::UPDATE_SOURCE_DRIVE)
             || update_flag.contains(UpdateFlag::UPDATE_SOURCE_FLAG);
+        self.field_pending |= update_field;
 
-        let filed_image = self.slice_viewer.field_image_view();
-        let slice_future = if update_field {
-            let after_compute = self
+        let mut compute_future = before_future.boxed();
+        if update_field {
+            compute_future = self
                 .field_compute_pipeline
                 .compute(
-                    filed_image,
+                    self.slice_viewer.field_image_view(),
+                    self.slice_viewer.field(),
                     self.slice_viewer.model(),
                     &self.sources,
                     &self.setting.viewer_setting,
                 )
-                .join(before_future);
-            after_compute
-                .then_execute(renderer.queue(), command_buffer)
-                .unwrap()
-                .boxed()
-        } else {
-            before_future
-                .then_execute(renderer.queue(), command_buffer)
-                .unwrap()
-                .boxed()
-        };
+                .join(compute_future)
+                .boxed();
+        }
+        for slice in self.slices.iter_mut() {
+            if let Some(future) = slice.compute(&self.sources, &self.setting.viewer_setting) {
+                compute_future = future.join(compute_future).boxed();
+            }
+        }
+        if self.volume_pending && self.setting.volume.enable && !self.sources.is_empty() {
+            self.volume_pending = false;
+            let setting = self.volume_compute_setting();
+            let volume = *self.volume_viewer.volume();
+            compute_future = self
+                .volume_pipeline
+                .compute_grid(
+                    self.volume_viewer.colors(),
+                    self.volume_viewer.field(),
+                    &self.volume_viewer.model(),
+                    volume.dims(),
+                    volume.effective_voxel_size(),
+                    &self.sources,
+                    &setting,
+                )
+                .join(compute_future)
+                .boxed();
+        }
+        let slice_future = compute_future
+            .then_execute(renderer.queue(), command_buffer)
+            .unwrap()
+            .boxed();
         let mut cmd_buf_builder = AutoCommandBufferBuilder::primary(
             renderer.device(),
             renderer.queue().family(),
@@ -289,97 +473,95 @@ impl App {
             &self.axis,
             UpdateFlag::all(),
         );
+        let volume_setting = self.volume_compute_setting();
+        self.volume_pipeline
+            .update(&self.sources, UpdateFlag::all(), &volume_setting);
+        if let Err(e) = self.volume_viewer.update(
+            render,
+            &view_projection,
+            &self.setting.viewer_setting,
+            &self.setting.volume,
+            UpdateFlag::all(),
+        ) {
+            self.log(Record::error(format!(
+                "failed to allocate the volume: {}",
+                e
+            )));
+        }
+        self.volume_pending = true;
+        self.slices = self
+            .setting
+            .slices
+            .iter()
+            .map(|&s| {
+                Slice::new(
+                    render,
+                    s,
+                    &self.setting.viewer_setting,
+                    &self.custom_color_map,
+                )
+            })
+            .collect();
 
         self.view_projection = view_projection;
     }
 
     fn handle_autd(&mut self, autd_server: &mut AUTDServer) -> UpdateFlag {
         let mut update_flag = UpdateFlag::empty();
-        autd_server.update(|event, emulator| match event {
+        autd_server.update(|event, emulator, packet| match event {
             AUTDEvent::Geometries(geometries) => {
-                self.sources.clear();
-                self.axis.clear();
-                self.dev_num = geometries.len();
-                if self.setting.show.len() < self.dev_num {
-                    self.setting.show.resize(self.dev_num, true);
-                }
-                if self.setting.enable.len() < self.dev_num {
-                    self.setting.enable.resize(self.dev_num, true);
-                }
-                if self.setting.show_axis.len() < self.dev_num {
-                    self.setting.show_axis.resize(self.dev_num, false);
-                }
-                for (i, geometry) in geometries.iter().enumerate() {
-                    let frequencies = emulator
-                        .fpga(i)
-                        .cycles()
-                        .map(|c| (FPGA_CLK_FREQ as f64 / c as f64) as f32);
-                    for (j, (pos, dir)) in geometry.make_autd_transducers().iter().enumerate() {
-                        let enable = if self.setting.enable[i] { 1.0 } else { 0.0 };
-                        let visible = if self.setting.show[i] { 1.0 } else { 0.0 };
-                        self.sources.add(
-                            *pos,
-                            *dir,
-                            Drive::new(
-                                0.0,
-                                0.0,
-                                enable,
-                                frequencies[j],
-                                self.setting.viewer_setting.sound_speed,
-                            ),
-                            visible,
-                        );
-                    }
-                    let mut axis = Axis3D::new(
-                        geometry.origin,
-                        geometry.right,
-                        geometry.up,
-                        vecmath::vec3_cross(geometry.right, geometry.up),
-                    );
-                    axis.show = self.setting.show_axis[i];
-                    self.axis.push(axis);
-                }
-                self.log("init geometry");
+                let layouts: Vec<_> = geometries
+                    .into_iter()
+                    .map(DeviceLayout::from_geometry)
+                    .collect();
+                self.init_geometry(&layouts, emulator);
+                self.fpga_states = FpgaState::collect(emulator);
+                self.history.capture(packet, emulator, self.static_mod);
+                self.log(Record::info("init geometry").kind(EventKind::Geometries));
                 update_flag |= UpdateFlag::INIT_SOURCE;
                 update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
                 update_flag |= UpdateFlag::INIT_AXIS;
             }
             AUTDEvent::Clear => {
-                self.sources
-                    .drives_mut()
-                    .zip(
-                        emulator
-                            .cpus()
-                            .iter()
-                            .flat_map(|cpu| cpu.fpga().drives()[0].0),
-                    )
-                    .zip(
-                        emulator
-                            .cpus()
-                            .iter()
-                            .flat_map(|cpu| cpu.fpga().drives()[0].1),
-                    )
-                    .zip(emulator.cpus().iter().flat_map(|cpu| cpu.fpga().cycles()))
-                    .for_each(|(((drive, duty), phase), cycle)| {
-                        drive.amp = duty.duty as f32 / cycle as f32;
-                        drive.phase = 2.0 * PI * phase.phase as f32 / cycle as f32;
-                        drive.set_wave_number(
-                            FPGA_CLK_FREQ as f32 / cycle as f32,
-                            self.setting.viewer_setting.sound_speed,
-                        );
-                    });
-                self.modulation = emulator.fpga(0).modulation();
-                self.log("clear");
+                self.load_initial_drives(emulator);
+                self.fpga_states = FpgaState::collect(emulator);
+                self.history.capture(packet, emulator, self.static_mod);
+                self.log(Record::info("clear").kind(EventKind::Clear));
                 update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
             }
+            AUTDEvent::PowerCycle(keep_geometry) => {
+                update_flag |= self.on_power_cycle(emulator, keep_geometry);
+                self.log(
+                    Record::info(if keep_geometry {
+                        "power cycle (geometry kept)"
+                    } else {
+                        "power cycle (geometry cleared)"
+                    })
+                    .kind(EventKind::PowerCycle),
+                );
+            }
+            AUTDEvent::Reboot(idx) => {
+                if idx >= emulator.cpus().len() {
+                    self.log(
+                        Record::error(format!("reboot: no device {}", idx)).kind(EventKind::Reboot),
+                    );
+                    return;
+                }
+                update_flag |= self.on_reboot(emulator, idx);
+                self.log(
+                    Record::warn("rebooted")
+                        .device(Some(idx))
+                        .kind(EventKind::Reboot),
+                );
+            }
             AUTDEvent::RequestCpuVersion => {
-                self.log("req cpu ver");
+                self.log(Record::info("req cpu ver").kind(EventKind::RequestCpuVersion));
             }
             AUTDEvent::RequestFpgaVersion => {
-                self.log("req fpga ver");
+                self.log(Record::info("req fpga ver").kind(EventKind::RequestFpgaVersion));
             }
             AUTDEvent::RequestFpgaFunctions => {
-                self.log("req fpga functions");
+                self.log(Record::info("req fpga functions").kind(EventKind::RequestFpgaFunctions));
             }
             AUTDEvent::Normal(flag) => {
                 if emulator.cpus().is_empty() {
@@ -387,28 +569,12 @@ impl App {
                 }
 
                 if flag.contains(CPUControlFlags::MOD_END) {
-                    self.modulation = emulator.fpga(0).modulation();
-                    if !self.modulation.0.is_empty() {
-                        let v = self.modulation.0[0];
-                        if self.modulation.0.iter().all(|&m| m == v) {
-                            self.static_mod = v as f32 / 255.0;
-                        } else {
-                            self.static_mod = 1.0;
-                        }
-                    } else {
-                        self.static_mod = 1.0;
-                    }
+                    self.load_modulations(emulator);
+                    self.update_static_mod();
                 }
 
-                self.is_legacy_mode = emulator.cpu(0).fpga().is_legacy_mode();
-                self.is_stm_mode = emulator.cpu(0).fpga().is_stm_mode();
-                self.is_gain_stm_mode = emulator.cpu(0).fpga().is_stm_gain_mode();
-                self.is_force_fan = emulator.cpu(0).fpga().is_force_fan();
-
-                self.stm_freq_div = emulator.cpu(0).fpga().stm_frequency_division();
-                self.silencer_cycle = emulator.cpu(0).fpga().silencer_cycle();
-                self.silencer_step = emulator.cpu(0).fpga().silencer_step();
-                self.point_stm_sound_speed = emulator.cpu(0).fpga().sound_speed();
+                self.load_fpga_flags(emulator);
+                self.fpga_states = FpgaState::collect(emulator);
 
                 if !flag.contains(CPUControlFlags::CONFIG_EN_N)
                     && flag.contains(CPUControlFlags::CONFIG_SYNC)
@@ -438,172 +604,1507 @@ impl App {
                     self.update_drive(0);
                 }
 
-                self.log("update drive");
+                self.history.capture(packet, emulator, self.static_mod);
+                self.log(Record::debug("update drive").kind(EventKind::Normal));
+                self.check_consistency(emulator);
                 update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
             }
         });
+
+        if let Some(msg) = autd_server.take_breakpoint_hit() {
+            self.log(Record::info(msg));
+        }
+        for (dev, msg) in autd_server.take_fault_hits() {
+            self.log(Record::warn(msg).device(Some(dev)));
+        }
+
+        // live updates must not overwrite the past state being viewed
+        if self.history_cursor.is_some() && update_flag.contains(UpdateFlag::UPDATE_SOURCE_DRIVE) {
+            self.update_drive(self.stm_idx as usize);
+        }
         update_flag
     }
 
-    fn update_view(&mut self, renderer: &mut Renderer, update_flag: UpdateFlag) {
-        self.trans_viewer.update(
-            renderer,
-            &self.view_projection,
-            &self.setting.viewer_setting,
-            &self.sources,
-            update_flag,
-        );
-        self.dir_viewer.update(
-            renderer,
-            &self.view_projection,
-            &self.setting.viewer_setting,
-            &self.axis,
-            update_flag,
-        );
-        self.slice_viewer.update(
-            renderer,
-            &self.view_projection,
-            &self.setting.viewer_setting,
-            update_flag,
-        );
-        self.field_compute_pipeline.update(
-            &self.sources,
-            update_flag,
-            &self.setting.viewer_setting,
-        );
+    fn init_geometry(&mut self, layouts: &[DeviceLayout], emulator: &Emulator) {
+        self.layouts = layouts.to_vec();
+        self.history.clear();
+        self.history_cursor = None;
+        self.sources.clear();
+        self.axis.clear();
+        self.dev_num = layouts.len();
+        if self.setting.show.len() < self.dev_num {
+            self.setting.show.resize(self.dev_num, true);
+        }
+        if self.setting.enable.len() < self.dev_num {
+            self.setting.enable.resize(self.dev_num, true);
+        }
+        if self.setting.show_axis.len() < self.dev_num {
+            self.setting.show_axis.resize(self.dev_num, false);
+        }
+        for (i, layout) in layouts.iter().enumerate() {
+            let frequencies = emulator
+                .fpga(i)
+                .cycles()
+                .map(|c| (FPGA_CLK_FREQ as f64 / c as f64) as f32);
+            for (j, (pos, dir)) in layout.transducers.iter().enumerate() {
+                let enable = if self.setting.enable[i] { 1.0 } else { 0.0 };
+                let visible = if self.setting.show[i] { 1.0 } else { 0.0 };
+                self.sources.add(
+                    *pos,
+                    *dir,
+                    Drive::new(
+                        0.0,
+                        0.0,
+                        enable,
+                        frequencies[j],
+                        self.setting.viewer_setting.sound_speed,
+                    ),
+                    visible,
+                );
+            }
+            let mut axis = Axis3D::new(
+                layout.axis.origin,
+                layout.axis.right,
+                layout.axis.up,
+                vecmath::vec3_cross(layout.axis.right, layout.axis.up),
+            );
+            axis.show = self.setting.show_axis[i];
+            self.axis.push(axis);
+        }
+        // the devices start from their initial state
+        self.drives = emulator
+            .cpus()
+            .iter()
+            .map(|cpu| cpu.fpga().drives())
+            .collect();
+        self.load_modulations(emulator);
     }
 
-    fn update_camera(&mut self, renderer: &mut Renderer, io: &Io) -> UpdateFlag {
-        let mut update_flag = UpdateFlag::empty();
+    // reads back the field computed in the previous frame
+    fn update_field_stats(&mut self) -> UpdateFlag {
+        if !self.field_pending {
+            return UpdateFlag::empty();
+        }
+        let field = match self.slice_viewer.read_field() {
+            Some(field) => field,
+            None => return UpdateFlag::empty(),
+        };
+        self.field_pending = false;
+
+        let stats = FieldStats::new(&field);
+        self.session_max = self.session_max.max(stats.max);
+        let scale = match self.setting.color_scale_mode {
+            ScaleMode::Fixed => None,
+            ScaleMode::SliceMax => Some(stats.max),
+            ScaleMode::Percentile => Some(stats.percentile(self.setting.color_scale_percentile)),
+            ScaleMode::SessionMax => Some(self.session_max),
+        };
+        self.slice_stats = Some(stats);
+        self.update_contours();
+
+        let color_scale = &mut self.setting.viewer_setting.color_scale;
+        match scale {
+            Some(scale) if scale > 0.0 && (scale - *color_scale).abs() > 1e-3 * scale => {
+                *color_scale = scale;
+                UpdateFlag::UPDATE_COLOR_MAP
+            }
+            _ => UpdateFlag::empty(),
+        }
+    }
 
-        let mouse_wheel = io.mouse_wheel;
-        if !io.want_capture_mouse && mouse_wheel != 0.0 {
-            let trans = vecmath::vec3_scale(
-                renderer.camera.forward,
-                -mouse_wheel * self.setting.viewer_setting.camera_move_speed,
-            );
-            self.setting.viewer_setting.camera_pos =
-                vecmath::vec3_add(self.setting.viewer_setting.camera_pos, trans);
-            renderer.camera.position = self.setting.viewer_setting.camera_pos;
-            self.view_projection = renderer.get_view_projection(&self.setting.viewer_setting);
-            update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+    fn color_scale_setting(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let names: Vec<&str> = ScaleMode::ALL.iter().map(|m| m.name()).collect();
+        let mut idx = ScaleMode::ALL
+            .iter()
+            .position(|&m| m == self.setting.color_scale_mode)
+            .unwrap_or(0);
+        if ui.combo_simple_string("Auto scale", &mut idx, &names) {
+            self.setting.color_scale_mode = ScaleMode::ALL[idx];
+            self.field_pending = true;
+        }
+        match self.setting.color_scale_mode {
+            ScaleMode::Percentile => {
+                if Slider::new("Percentile", 0.0, 100.0)
+                    .build(ui, &mut self.setting.color_scale_percentile)
+                {
+                    self.field_pending = true;
+                }
+            }
+            ScaleMode::SessionMax => {
+                if ui.small_button("reset session max") {
+                    self.session_max = 0.0;
+                    // the next readback sets the scale to the current slice max
+                    update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+                }
+            }
+            _ => {}
         }
-        let mouse_delta = io.mouse_delta;
-        if !io.want_capture_mouse && io.mouse_down[0] && !vecmath_util::is_zero(&mouse_delta) {
-            if io.key_shift {
-                let mouse_delta = vecmath::vec2_scale(
-                    mouse_delta,
-                    self.setting.viewer_setting.camera_move_speed / 3000.0,
-                );
-                let trans_x = vecmath::vec3_scale(renderer.camera.right, mouse_delta[0]);
-                let trans_y = vecmath::vec3_scale(renderer.camera.up, -mouse_delta[1]);
-                let to =
-                    vecmath::vec3_add(vecmath::vec3_add(trans_x, trans_y), renderer.camera.forward);
-                let rot = vecmath_util::quaternion_to(renderer.camera.forward, to);
 
-                renderer.camera.forward = quaternion::rotate_vector(rot, renderer.camera.forward);
-                renderer.camera.up = quaternion::rotate_vector(rot, renderer.camera.up);
-                renderer.camera.right = quaternion::rotate_vector(rot, renderer.camera.right);
-                let rotm = [
-                    renderer.camera.right,
-                    renderer.camera.up,
-                    renderer.camera.forward,
-                ];
-                self.setting.viewer_setting.camera_angle =
-                    camera_helper::rot_mat_to_euler_angles(&rotm);
+        if let Some(stats) = &self.slice_stats {
+            let unit = if self.setting.colorbar_pascal {
+                self.setting.source_amplitude
             } else {
-                let mouse_delta = vecmath::vec2_scale(
-                    mouse_delta,
-                    self.setting.viewer_setting.camera_move_speed / 10.0,
+                1.0
+            };
+            ui.text(format!(
+                "Slice min/max/mean: {:.4}/{:.4}/{:.4}{}",
+                stats.min * unit,
+                stats.max * unit,
+                stats.mean * unit,
+                if self.setting.colorbar_pascal {
+                    " [Pa]"
+                } else {
+                    ""
+                }
+            ));
+        }
+        update_flag
+    }
+
+    fn update_contours(&mut self) {
+        self.contours.clear();
+        let stats = match &self.slice_stats {
+            Some(stats) if self.setting.show_contours => stats,
+            _ => return,
+        };
+        let (unit, unit_name) = if self.setting.colorbar_pascal {
+            (self.setting.source_amplitude, "Pa")
+        } else {
+            (1.0, "")
+        };
+        let [width, height] = self.slice_viewer.slice_size();
+        let mode = self.setting.contour_mode;
+        self.contours = self
+            .setting
+            .contour_levels
+            .iter()
+            .map(|&level| Contour {
+                label: mode.label(level, unit_name),
+                segments: contour::trace(
+                    stats.amplitudes(),
+                    width as usize,
+                    height as usize,
+                    mode.amplitude(level, stats.max, unit),
+                ),
+            })
+            .collect();
+        self.contour_size = [width, height];
+    }
+
+    fn contour_setting(&mut self, ui: &Ui) {
+        if !CollapsingHeader::new("Contours").build(ui) {
+            return;
+        }
+        let mut changed = ui.checkbox("Show contours", &mut self.setting.show_contours);
+        ui.same_line();
+        ui.checkbox(
+            "Contours in saved images",
+            &mut self.setting.contour_in_image,
+        );
+        let names: Vec<&str> = ContourMode::ALL.iter().map(|m| m.name()).collect();
+        let mut idx = ContourMode::ALL
+            .iter()
+            .position(|&m| m == self.setting.contour_mode)
+            .unwrap_or(0);
+        if ui.combo_simple_string("Levels", &mut idx, &names) {
+            self.setting.contour_mode = ContourMode::ALL[idx];
+            changed = true;
+        }
+        let mut remove = None;
+        for (i, level) in self.setting.contour_levels.iter_mut().enumerate() {
+            changed |= Drag::new(&format!("##contour level {}", i))
+                .speed(0.1)
+                .build(ui, level);
+            ui.same_line();
+            if ui.small_button(&format!("remove##contour {}", i)) {
+                remove = Some(i);
+            }
+        }
+        if let Some(i) = remove {
+            self.setting.contour_levels.remove(i);
+            changed = true;
+        }
+        if ui.small_button("add level") {
+            let level = match self.setting.contour_mode {
+                ContourMode::Fixed => self.setting.viewer_setting.color_scale / 2.0,
+                ContourMode::Relative => 0.5,
+                ContourMode::RelativeDb => -3.0,
+            };
+            self.setting.contour_levels.push(level);
+            changed = true;
+        }
+        if changed {
+            self.update_contours();
+        }
+    }
+
+    fn draw_contours(&self, ui: &Ui) {
+        let display_size = ui.io().display_size;
+        let [width, height] = self.contour_size;
+        let pixel_size = self.setting.viewer_setting.slice_pixel_size as f32;
+        let model = *self.slice_viewer.model();
+        let to_screen = |p: Vector2| {
+            let x = (p[0] - width as f32 / 2.0) * pixel_size;
+            let y = (p[1] - height as f32 / 2.0) * pixel_size;
+            let q = vecmath::col_mat4_transform(model, [x, y, 0.0, 1.0]);
+            camera_helper::world_to_screen([q[0], q[1], q[2]], display_size, &self.view_projection)
+        };
+        let draw_list = ui.get_background_draw_list();
+        for contour in &self.contours {
+            for &[a, b] in &contour.segments {
+                if let (Some(a), Some(b)) = (to_screen(a), to_screen(b)) {
+                    draw_list.add_line(a, b, [1.0, 1.0, 1.0, 1.0]).build();
+                }
+            }
+            if let Some(p) = contour.label_pos().and_then(to_screen) {
+                draw_list.add_text(
+                    [p[0] + 2.0, p[1] - ui.text_line_height()],
+                    [1.0, 1.0, 1.0, 1.0],
+                    &contour.label,
                 );
-                let trans_x = vecmath::vec3_scale(renderer.camera.right, -mouse_delta[0]);
-                let trans_y = vecmath::vec3_scale(renderer.camera.up, mouse_delta[1]);
-                let trans = vecmath::vec3_add(trans_x, trans_y);
-                self.setting.viewer_setting.camera_pos =
-                    vecmath::vec3_add(self.setting.viewer_setting.camera_pos, trans);
-                renderer.camera.position = self.setting.viewer_setting.camera_pos;
             }
-            self.view_projection = renderer.get_view_projection(&self.setting.viewer_setting);
-            update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
         }
+    }
 
-        update_flag
+    fn update_profile(&mut self) {
+        self.profile_pending = false;
+        let line = self.setting.profile_line;
+        self.profile = Some(Profile::sample(
+            &self.sources,
+            line.end_points(self.slice_viewer.model()),
+            line.samples,
+        ));
     }
 
-    fn update_ui(&mut self, ui: &Ui, renderer: &mut Renderer) -> UpdateFlag {
-        let mut update_flag = UpdateFlag::empty();
-        self.save_image = false;
-        Window::new("Controller").build(ui, || {
-            TabBar::new("Settings").build(ui, || {
-                TabItem::new("Slice").build(ui, || {
-                    ui.text("Slice size");
-                    if Slider::new("Slice width", 1, 1000)
-                        .build(ui, &mut self.setting.viewer_setting.slice_width)
-                    {
-                        update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
-                    }
-                    if Slider::new("Slice heigh", 1, 1000)
-                        .build(ui, &mut self.setting.viewer_setting.slice_height)
-                    {
-                        update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
-                    }
-                    if Slider::new("Pixel size", 1, 8)
-                        .build(ui, &mut self.setting.viewer_setting.slice_pixel_size)
-                    {
-                        update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
-                    }
+    fn profile_setting(&mut self, ui: &Ui) {
+        if !CollapsingHeader::new("Line profile").build(ui) {
+            return;
+        }
+        let line = &mut self.setting.profile_line;
+        let mut changed = ui.checkbox("Show profile", &mut self.setting.show_profile);
+        let names: Vec<&str> = LineSpace::ALL.iter().map(|m| m.name()).collect();
+        let mut idx = LineSpace::ALL
+            .iter()
+            .position(|&m| m == line.space)
+            .unwrap_or(0);
+        if ui.combo_simple_string("Line in", &mut idx, &names) {
+            line.space = LineSpace::ALL[idx];
+            changed = true;
+        }
+        match line.space {
+            LineSpace::Slice => {
+                let mut start = [line.start[0], line.start[1]];
+                let mut end = [line.end[0], line.end[1]];
+                if Drag::new("start [mm]##profile").build_array(ui, &mut start) {
+                    line.start = [start[0], start[1], 0.0];
+                    changed = true;
+                }
+                if Drag::new("end [mm]##profile").build_array(ui, &mut end) {
+                    line.end = [end[0], end[1], 0.0];
+                    changed = true;
+                }
+                ui.text_disabled("(0, 0) is the center of the slice");
+            }
+            LineSpace::World => {
+                changed |= Drag::new("start [mm]##profile").build_array(ui, &mut line.start);
+                changed |= Drag::new("end [mm]##profile").build_array(ui, &mut line.end);
+            }
+        }
+        changed |= Drag::new("samples##profile")
+            .range(2, 4096)
+            .build(ui, &mut line.samples);
+        if self.slice_markers.len() >= 2 {
+            if ui.small_button("through markers 0 and 1") {
+                line.space = LineSpace::World;
+                line.start = self.slice_markers[0];
+                line.end = self.slice_markers[1];
+                changed = true;
+            }
+        } else {
+            ui.text_disabled("pin two slice markers to put the line through them");
+        }
+        self.profile_pending |= changed;
 
-                    ui.separator();
-                    ui.text("Slice position");
-                    if Drag::new("Slice X").build(ui, &mut self.setting.viewer_setting.slice_pos[0])
-                    {
-                        self.slice_viewer
-                            .move_to(self.setting.viewer_setting.slice_pos);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-                    if Drag::new("Slice Y").build(ui, &mut self.setting.viewer_setting.slice_pos[1])
-                    {
-                        self.slice_viewer
-                            .move_to(self.setting.viewer_setting.slice_pos);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-                    if Drag::new("Slice Z").build(ui, &mut self.setting.viewer_setting.slice_pos[2])
-                    {
-                        self.slice_viewer
-                            .move_to(self.setting.viewer_setting.slice_pos);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
+        InputText::new(ui, "path to profile", &mut self.setting.profile_export_path).build();
+        ui.same_line();
+        if ui.small_button("export csv##profile") {
+            self.export_profile();
+        }
+    }
 
-                    ui.separator();
-                    ui.text("Slice Rotation");
-                    if AngleSlider::new("Slice RX")
-                        .range_degrees(0.0, 360.0)
-                        .build(ui, &mut self.setting.viewer_setting.slice_angle[0])
-                    {
-                        self.slice_viewer
-                            .rotate_to(self.setting.viewer_setting.slice_angle);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-                    if AngleSlider::new("Slice RY")
-                        .range_degrees(0.0, 360.0)
-                        .build(ui, &mut self.setting.viewer_setting.slice_angle[1])
-                    {
-                        self.slice_viewer
-                            .rotate_to(self.setting.viewer_setting.slice_angle);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
-                    if AngleSlider::new("Slice RZ")
-                        .range_degrees(0.0, 360.0)
-                        .build(ui, &mut self.setting.viewer_setting.slice_angle[2])
-                    {
-                        self.slice_viewer
-                            .rotate_to(self.setting.viewer_setting.slice_angle);
-                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
-                    }
+    fn profile_window(&mut self, ui: &Ui) {
+        let display_size = ui.io().display_size;
+        let [start, end] = self
+            .setting
+            .profile_line
+            .end_points(self.slice_viewer.model());
+        let to_screen = |p| camera_helper::world_to_screen(p, display_size, &self.view_projection);
+        if let (Some(a), Some(b)) = (to_screen(start), to_screen(end)) {
+            let draw_list = ui.get_background_draw_list();
+            draw_list
+                .add_line(a, b, [1.0, 1.0, 0.0, 1.0])
+                .thickness(2.0)
+                .build();
+            draw_list.add_circle(a, 3.0, [1.0, 1.0, 0.0, 1.0]).build();
+        }
 
-                    ui.separator();
-                    ui.text("Slice color setting");
-                    if Drag::new("Color scale")
+        let profile = match &self.profile {
+            Some(profile) => profile,
+            None => return,
+        };
+        let (unit, unit_name) = if self.setting.colorbar_pascal {
+            (self.setting.source_amplitude, " [Pa]")
+        } else {
+            (1.0, "")
+        };
+        let mut opened = true;
+        Window::new("Line profile")
+            .opened(&mut opened)
+            .size([420.0, 260.0], Condition::FirstUseEver)
+            .build(ui, || {
+                let values: Vec<f32> = profile.amplitudes.iter().map(|v| v * unit).collect();
+                let width = ui.content_region_avail()[0];
+                PlotLines::new(ui, "##profile", &values)
+                    .graph_size([width, 120.0])
+                    .scale_min(0.0)
+                    .build();
+                ui.text(format!(
+                    "Length: {:.3} [mm]",
+                    profile.distance(values.len().saturating_sub(1))
+                ));
+                match profile.peak() {
+                    Some((i, max)) => ui.text(format!(
+                        "Peak: {:.4}{} at {:.3} [mm]",
+                        max * unit,
+                        unit_name,
+                        profile.distance(i)
+                    )),
+                    None => ui.text("Peak: -"),
+                }
+                match profile.fwhm() {
+                    Some(fwhm) => ui.text(format!("FWHM: {:.3} [mm]", fwhm)),
+                    None => ui.text("FWHM: - (main lobe exceeds the line)"),
+                }
+                match profile.side_lobe_level() {
+                    Some(level) => ui.text(format!("Side lobe level: {:.2} [dB]", level)),
+                    None => ui.text("Side lobe level: -"),
+                }
+                ui.text_disabled("the line starts at the circle");
+            });
+        if !opened {
+            self.setting.show_profile = false;
+        }
+    }
+
+    fn export_profile(&mut self) {
+        if self.profile.is_none() || self.profile_pending {
+            self.update_profile();
+        }
+        let result = match &self.profile {
+            Some(profile) => profile.save_csv(
+                &self.setting.profile_export_path,
+                self.setting.source_amplitude,
+            ),
+            None => return,
+        };
+        match result {
+            Ok(path) => self.log(Record::info(format!("export profile: {}", path.display()))),
+            Err(e) => self.log(Record::error(format!("failed to export profile: {}", e))),
+        }
+    }
+
+    fn color_map_setting(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let names: Vec<&str> = ColorMapKind::ALL.iter().map(|c| c.name()).collect();
+        let mut idx = ColorMapKind::ALL
+            .iter()
+            .position(|&c| c == self.setting.viewer_setting.color_map)
+            .unwrap_or(0);
+        if ui.combo_simple_string("Color map", &mut idx, &names) {
+            self.setting.viewer_setting.color_map = ColorMapKind::ALL[idx];
+            if self.setting.viewer_setting.color_map == ColorMapKind::Custom
+                && self.custom_color_map.is_empty()
+            {
+                self.load_custom_color_map();
+            }
+            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+        if self.setting.viewer_setting.color_map == ColorMapKind::Custom {
+            InputText::new(ui, "path to color map", &mut self.setting.color_map_path).build();
+            ui.same_line();
+            if ui.small_button("load##color map") {
+                update_flag |= self.load_custom_color_map();
+            }
+        }
+
+        ui.checkbox("Show colorbar", &mut self.setting.show_colorbar);
+        ui.same_line();
+        ui.checkbox(
+            "Colorbar in saved images",
+            &mut self.setting.colorbar_in_image,
+        );
+        ui.checkbox("Colorbar in Pa", &mut self.setting.colorbar_pascal);
+        if self.setting.colorbar_pascal {
+            Drag::new("Source amplitude [Pa at 1 mm]")
+                .range(0.0, f32::INFINITY)
+                .build(ui, &mut self.setting.source_amplitude);
+        }
+        update_flag
+    }
+
+    fn slices_setting(&mut self, ui: &Ui, renderer: &Renderer) {
+        let quantities: Vec<&str> = SliceQuantity::ALL.iter().map(|q| q.name()).collect();
+        let color_maps: Vec<&str> = ColorMapKind::ALL.iter().map(|c| c.name()).collect();
+        let mut remove = None;
+        let mut export = None;
+        for (i, slice) in self.slices.iter_mut().enumerate() {
+            if !CollapsingHeader::new(&format!("Slice {}", i)).build(ui) {
+                continue;
+            }
+            let mut flag = UpdateFlag::empty();
+            let s = &mut slice.setting;
+            ui.checkbox(&format!("show##slice {}", i), &mut s.visible);
+            for (k, axis) in ["X", "Y", "Z"].iter().enumerate() {
+                if Drag::new(&format!("{}##slice {}", axis, i)).build(ui, &mut s.pos[k]) {
+                    flag |= UpdateFlag::UPDATE_SLICE_POS;
+                }
+            }
+            for (k, axis) in ["RX", "RY", "RZ"].iter().enumerate() {
+                if AngleSlider::new(&format!("{}##slice {}", axis, i))
+                    .range_degrees(0.0, 360.0)
+                    .build(ui, &mut s.angle[k])
+                {
+                    flag |= UpdateFlag::UPDATE_SLICE_POS;
+                }
+            }
+            let mut resized =
+                Slider::new(&format!("width##slice {}", i), 1, 1000).build(ui, &mut s.width);
+            resized |=
+                Slider::new(&format!("height##slice {}", i), 1, 1000).build(ui, &mut s.height);
+            resized |=
+                Slider::new(&format!("pixel size##slice {}", i), 1, 8).build(ui, &mut s.pixel_size);
+            if resized {
+                flag |= UpdateFlag::UPDATE_SLICE_SIZE;
+            }
+            let mut idx = SliceQuantity::ALL
+                .iter()
+                .position(|&q| q == s.quantity)
+                .unwrap_or(0);
+            if ui.combo_simple_string(&format!("quantity##slice {}", i), &mut idx, &quantities) {
+                s.quantity = SliceQuantity::ALL[idx];
+                flag |= UpdateFlag::UPDATE_COLOR_MAP;
+            }
+            let mut idx = ColorMapKind::ALL
+                .iter()
+                .position(|&c| c == s.color_map)
+                .unwrap_or(0);
+            if ui.combo_simple_string(&format!("color map##slice {}", i), &mut idx, &color_maps) {
+                s.color_map = ColorMapKind::ALL[idx];
+                flag |= UpdateFlag::UPDATE_COLOR_MAP;
+            }
+            let mut recolored = Drag::new(&format!("color scale##slice {}", i))
+                .speed(0.1)
+                .range(0.0, f32::INFINITY)
+                .build(ui, &mut s.color_scale);
+            recolored |=
+                Slider::new(&format!("alpha##slice {}", i), 0.0, 1.0).build(ui, &mut s.alpha);
+            recolored |= ui.checkbox(&format!("dB##slice {}", i), &mut s.db_mode);
+            if recolored {
+                flag |= UpdateFlag::UPDATE_COLOR_MAP;
+            }
+            if s.db_mode {
+                ui.same_line();
+                if Drag::new(&format!("dynamic range [dB]##slice {}", i))
+                    .range(1.0, 200.0)
+                    .speed(0.5)
+                    .build(ui, &mut s.db_range)
+                {
+                    flag |= UpdateFlag::UPDATE_COLOR_MAP;
+                }
+            }
+            slice.mark(flag);
+
+            if ui.small_button(&format!("export npy##slice {}", i)) {
+                export = Some((i, false));
+            }
+            ui.same_line();
+            if ui.small_button(&format!("export csv##slice {}", i)) {
+                export = Some((i, true));
+            }
+            ui.same_line();
+            if ui.small_button(&format!("remove##slice {}", i)) {
+                remove = Some(i);
+            }
+        }
+        if let Some((i, csv)) = export {
+            self.export_field(Some(i), csv);
+        }
+        if let Some(i) = remove {
+            self.slices.remove(i);
+        }
+        if ui.small_button("add slice") {
+            let setting = SliceSetting::from_viewer_setting(&self.setting.viewer_setting);
+            self.slices.push(Slice::new(
+                renderer,
+                setting,
+                &self.setting.viewer_setting,
+                &self.custom_color_map,
+            ));
+        }
+        ui.text_disabled("exported to <path to field>_slice<index>");
+        self.setting.slices = self.slices.iter().map(|s| s.setting).collect();
+    }
+
+    // the volume is always colored by amplitude with the color setting of the main slice
+    fn volume_compute_setting(&self) -> ViewerSettings {
+        ViewerSettings {
+            slice_quantity: SliceQuantity::Amplitude,
+            slice_alpha: 1.0,
+            ..self.setting.viewer_setting
+        }
+    }
+
+    fn volume_setting(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        let volume = &mut self.setting.volume;
+        if ui.checkbox("Enable volume", &mut volume.enable) {
+            update_flag |= UpdateFlag::UPDATE_VOLUME;
+        }
+
+        ui.separator();
+        ui.text("Bounding box");
+        for (k, axis) in ["X", "Y", "Z"].iter().enumerate() {
+            if Drag::new(&format!("Center {}", axis)).build(ui, &mut volume.center[k]) {
+                update_flag |= UpdateFlag::UPDATE_VOLUME;
+            }
+        }
+        for (k, axis) in ["X", "Y", "Z"].iter().enumerate() {
+            if Drag::new(&format!("Size {}", axis))
+                .range(1.0, 1000.0)
+                .build(ui, &mut volume.size[k])
+            {
+                update_flag |= UpdateFlag::UPDATE_VOLUME;
+            }
+        }
+        if Slider::new("Voxel size", 1, 10).build(ui, &mut volume.voxel_size) {
+            update_flag |= UpdateFlag::UPDATE_VOLUME;
+        }
+        let [w, h, d] = volume.dims();
+        ui.text(format!(
+            "{} x {} x {} = {} voxels",
+            w,
+            h,
+            d,
+            w as usize * h as usize * d as usize
+        ));
+        if volume.effective_voxel_size() != volume.voxel_size.max(1) {
+            ui.text_disabled(format!(
+                "voxel size is raised to {} to stay within {} voxels",
+                volume.effective_voxel_size(),
+                MAX_VOXELS
+            ));
+        }
+        if ui.small_button("center on slice") {
+            volume.center = vecmath_util::to_vec3(&self.setting.viewer_setting.slice_pos);
+            update_flag |= UpdateFlag::UPDATE_VOLUME;
+        }
+
+        ui.separator();
+        let names: Vec<&str> = VolumeRendering::ALL.iter().map(|r| r.name()).collect();
+        let mut idx = VolumeRendering::ALL
+            .iter()
+            .position(|&r| r == volume.rendering)
+            .unwrap_or(0);
+        if ui.combo_simple_string("Rendering", &mut idx, &names) {
+            volume.rendering = VolumeRendering::ALL[idx];
+        }
+        match volume.rendering {
+            VolumeRendering::RayMarching => {
+                Drag::new("Opacity [/mm]")
+                    .range(0.0, 10.0)
+                    .speed(0.001)
+                    .build(ui, &mut volume.opacity);
+            }
+            VolumeRendering::Isosurface => {
+                Slider::new("Surface alpha", 0.0, 1.0).build(ui, &mut volume.opacity);
+                Slider::new("Levels", 1, MAX_ISO_LEVELS as u32)
+                    .build(ui, &mut volume.iso_level_num);
+                for i in 0..volume.iso_level_num as usize {
+                    Drag::new(&format!("Level {}", i))
+                        .range(0.0, f32::INFINITY)
+                        .speed(0.01)
+                        .build(ui, &mut volume.iso_levels[i]);
+                }
+                ui.text_disabled("levels are relative to the color scale");
+            }
+        }
+
+        ui.separator();
+        ui.text("Export VTK");
+        InputText::new(ui, "path to vtk", &mut self.setting.vtk_export_path).build();
+        ui.checkbox("CPU solver", &mut self.setting.vtk_cpu_solver);
+        if self.vtk_export.is_some() {
+            ui.text_disabled("exporting...");
+        } else if ui.small_button("export vti/vtp") {
+            self.export_vtk();
+        }
+        ui.text_disabled("<path>.vti for the field, <path>_transducers.vtp for the transducers");
+        update_flag
+    }
+
+    // the GPU field is read here, while solving and writing run on a worker thread
+    fn start_vtk_export(&mut self) -> Result<()> {
+        let gpu_field = if self.setting.vtk_cpu_solver || !self.setting.volume.enable {
+            None
+        } else {
+            let field = self
+                .volume_viewer
+                .read_field()
+                .filter(|_| !self.volume_pending)
+                .ok_or_else(|| anyhow!("the volume is being computed"))?;
+            Some((*self.volume_viewer.volume(), field))
+        };
+        let volume = self.setting.volume;
+        let sources = self.sources.clone();
+        let source_amplitude = self.setting.source_amplitude;
+        let path = PathBuf::from(&self.setting.vtk_export_path);
+        self.vtk_export = Some(thread::spawn(move || {
+            let (volume, field) =
+                gpu_field.unwrap_or_else(|| (volume, vtk_export::solve(&sources, &volume)));
+            let vti = vtk_export::save_vti(&path, &volume, &field, source_amplitude)?;
+            let vtp =
+                vtk_export::save_vtp(field_export::with_suffix(&path, "_transducers"), &sources)?;
+            Ok([vti, vtp])
+        }));
+        Ok(())
+    }
+
+    fn export_vtk(&mut self) {
+        if let Err(e) = self.start_vtk_export() {
+            self.log(Record::error(format!("failed to export vtk: {}", e)));
+        }
+    }
+
+    fn poll_vtk_export(&mut self) {
+        let res = match self.vtk_export.take() {
+            Some(handle) if handle.is_finished() => handle
+                .join()
+                .unwrap_or_else(|_| Err(anyhow!("the export thread panicked"))),
+            handle => {
+                self.vtk_export = handle;
+                return;
+            }
+        };
+        match res {
+            Ok([vti, vtp]) => self.log(Record::info(format!(
+                "export vtk: {}, {}",
+                vti.display(),
+                vtp.display()
+            ))),
+            Err(e) => self.log(Record::error(format!("failed to export vtk: {}", e))),
+        }
+    }
+
+    fn load_custom_color_map(&mut self) -> UpdateFlag {
+        match color_map::load_custom(&self.setting.color_map_path) {
+            Ok(colors) if !colors.is_empty() => {
+                self.log(Record::info(format!(
+                    "load color map: {} ({} colors)",
+                    self.setting.color_map_path,
+                    colors.len()
+                )));
+                self.field_compute_pipeline
+                    .set_custom_color_map(colors.clone());
+                self.slices
+                    .iter_mut()
+                    .for_each(|slice| slice.set_custom_color_map(colors.clone()));
+                self.volume_pipeline.set_custom_color_map(colors.clone());
+                self.custom_color_map = colors;
+                UpdateFlag::UPDATE_COLOR_MAP
+            }
+            Ok(_) => {
+                self.log(Record::error("failed to load color map: no color"));
+                UpdateFlag::empty()
+            }
+            Err(e) => {
+                self.log(Record::error(format!("failed to load color map: {}", e)));
+                UpdateFlag::empty()
+            }
+        }
+    }
+
+    // ticks and unit of the colorbar for the quantity shown on the main slice
+    fn colorbar_ticks(&self) -> (Vec<(f32, String)>, &'static str) {
+        let setting = &self.setting.viewer_setting;
+        let scale = setting.color_scale;
+        let (scale, unit) = if self.setting.colorbar_pascal {
+            (scale * self.setting.source_amplitude, "Pa")
+        } else {
+            (scale, "")
+        };
+        match setting.slice_quantity {
+            SliceQuantity::Phase => (colorbar::ticks(-PI, PI), "rad"),
+            SliceQuantity::Real => (colorbar::ticks(-scale, scale), unit),
+            _ if setting.db_mode => (colorbar::ticks(-setting.db_range, 0.0), "dB"),
+            // relative to the intensity at the color scale
+            SliceQuantity::Intensity => (colorbar::ticks(0.0, 1.0), ""),
+            SliceQuantity::Amplitude => (colorbar::ticks(0.0, scale), unit),
+        }
+    }
+
+    fn export_slice(
+        viewer: &SliceViewer,
+        setting: &ViewerSettings,
+        quantity: Quantity,
+        source_amplitude: f32,
+        path: &str,
+        csv: bool,
+    ) -> Result<Vec<PathBuf>> {
+        let field = viewer
+            .read_field()
+            .ok_or_else(|| anyhow!("the field is being computed"))?;
+        let [width, height] = viewer.slice_size();
+        let data = SliceField {
+            meta: Metadata {
+                quantity,
+                width,
+                height,
+                pixel_size: setting.slice_pixel_size as f32,
+                slice_model: *viewer.model(),
+                frequency: setting.frequency,
+                sound_speed: setting.sound_speed,
+                source_amplitude,
+            },
+            field,
+        };
+        if csv {
+            data.save_csv(path).map(|p| vec![p])
+        } else {
+            data.save_npy(path)
+        }
+    }
+
+    // idx is None for the main slice, otherwise the index of the additional slice
+    fn export_field(&mut self, idx: Option<usize>, csv: bool) {
+        let result = match idx {
+            None => Self::export_slice(
+                &self.slice_viewer,
+                &self.setting.viewer_setting,
+                self.setting.field_export_quantity,
+                self.setting.source_amplitude,
+                &self.setting.field_export_path,
+                csv,
+            ),
+            Some(i) => {
+                let slice = &self.slices[i];
+                Self::export_slice(
+                    &slice.viewer,
+                    &slice.setting.viewer_setting(&self.setting.viewer_setting),
+                    self.setting.field_export_quantity,
+                    self.setting.source_amplitude,
+                    &format!("{}_slice{}", self.setting.field_export_path, i),
+                    csv,
+                )
+            }
+        };
+        match result {
+            Ok(paths) => {
+                let paths: Vec<_> = paths.iter().map(|p| p.display().to_string()).collect();
+                self.log(Record::info(format!("export field: {}", paths.join(", "))))
+            }
+            Err(e) => self.log(Record::error(format!("failed to export field: {}", e))),
+        }
+    }
+
+    fn load_geometry_file(&mut self, autd_server: &mut AUTDServer) -> UpdateFlag {
+        let layouts = match geometry_file::load(&self.setting.geometry_file) {
+            Ok(layouts) => layouts,
+            Err(e) => {
+                self.log(Record::error(format!("failed to load geometry: {}", e)));
+                return UpdateFlag::empty();
+            }
+        };
+        autd_server.set_device_num(layouts.len());
+        let emulator = autd_server.emulator();
+        self.init_geometry(&layouts, emulator);
+        self.fpga_states = FpgaState::collect(emulator);
+        self.log(Record::info(format!(
+            "load geometry: {} ({} devices)",
+            self.setting.geometry_file,
+            layouts.len()
+        )));
+        UpdateFlag::INIT_SOURCE | UpdateFlag::UPDATE_SOURCE_DRIVE | UpdateFlag::INIT_AXIS
+    }
+
+    fn scene(&self) -> Scene {
+        let snapshot = self.history_cursor.and_then(|id| self.history.find(id));
+        let (drives, cycles, modulations, static_mod) = match snapshot {
+            Some(s) => (&s.drives, &s.cycles, &s.modulations, s.static_mod),
+            None => (
+                &self.drives,
+                &self.cycles,
+                &self.modulations,
+                self.static_mod,
+            ),
+        };
+        let devices = DeviceScene::collect(
+            &self.layouts,
+            &self.fpga_states,
+            drives,
+            cycles,
+            modulations,
+        );
+        Scene {
+            devices,
+            static_mod,
+            stm_idx: self.stm_idx,
+            show: self.setting.show.clone(),
+            enable: self.setting.enable.clone(),
+            show_axis: self.setting.show_axis.clone(),
+            viewer_setting: self.setting.viewer_setting,
+            color_map_path: self.setting.color_map_path.clone(),
+            slices: self.setting.slices.clone(),
+            volume: self.setting.volume,
+            show_contours: self.setting.show_contours,
+            contour_mode: self.setting.contour_mode,
+            contour_levels: self.setting.contour_levels.clone(),
+        }
+    }
+
+    fn save_scene(&mut self) {
+        match self.scene().save(&self.setting.scene_path) {
+            Ok(()) => self.log(Record::info(format!(
+                "save scene: {}",
+                self.setting.scene_path
+            ))),
+            Err(e) => self.log(Record::error(format!("failed to save scene: {}", e))),
+        }
+    }
+
+    fn load_scene(&mut self, renderer: &mut Renderer, autd_server: &mut AUTDServer) -> UpdateFlag {
+        let scene = match Scene::load(&self.setting.scene_path) {
+            Ok(scene) => scene,
+            Err(e) => {
+                self.log(Record::error(format!("failed to load scene: {}", e)));
+                return UpdateFlag::empty();
+            }
+        };
+
+        self.setting.show = scene.show;
+        self.setting.enable = scene.enable;
+        self.setting.show_axis = scene.show_axis;
+        self.setting.viewer_setting = ViewerSettings {
+            vsync: self.setting.viewer_setting.vsync,
+            ..scene.viewer_setting
+        };
+        self.setting.color_map_path = scene.color_map_path;
+        self.setting.slices = scene.slices;
+        self.setting.volume = scene.volume;
+        self.setting.show_contours = scene.show_contours;
+        self.setting.contour_mode = scene.contour_mode;
+        self.setting.contour_levels = scene.contour_levels;
+
+        autd_server.set_device_num(scene.devices.len());
+        let layouts: Vec<_> = scene.devices.iter().map(|d| d.layout.clone()).collect();
+        self.init_geometry(&layouts, autd_server.emulator());
+
+        self.fpga_states = scene.devices.iter().map(|d| d.state.clone()).collect();
+        self.drives = scene.devices.iter().map(|d| d.drives()).collect();
+        self.cycles = self
+            .fpga_states
+            .iter()
+            .flat_map(|s| s.cycles.iter().copied())
+            .collect();
+        self.modulations = scene.devices.iter().map(|d| d.modulation()).collect();
+        self.update_modulation(self.modulations.first().cloned().unwrap_or_default());
+        if let Some(s) = self.fpga_states.first() {
+            self.is_legacy_mode = s.is_legacy_mode;
+            self.is_stm_mode = s.is_stm_mode;
+            self.is_gain_stm_mode = s.is_stm_gain_mode;
+            self.is_force_fan = s.is_force_fan;
+            self.stm_freq_div = s.stm_freq_div;
+            self.silencer_cycle = s.silencer_cycle;
+            self.silencer_step = s.silencer_step;
+            self.point_stm_sound_speed = s.sound_speed;
+        }
+        self.static_mod = scene.static_mod;
+        self.stm_idx = scene.stm_idx;
+        self.warnings.clear();
+        self.update_drive(self.stm_idx as usize);
+
+        self.reset(renderer);
+        let custom = std::iter::once(self.setting.viewer_setting.color_map)
+            .chain(self.setting.slices.iter().map(|s| s.color_map))
+            .any(|c| c == ColorMapKind::Custom);
+        if custom {
+            self.load_custom_color_map();
+        }
+        self.log(Record::info(format!(
+            "load scene: {}",
+            self.setting.scene_path
+        )));
+        UpdateFlag::all()
+    }
+
+    fn load_initial_drives(&mut self, emulator: &Emulator) {
+        self.sources
+            .drives_mut()
+            .zip(
+                emulator
+                    .cpus()
+                    .iter()
+                    .flat_map(|cpu| cpu.fpga().drives()[0].0),
+            )
+            .zip(
+                emulator
+                    .cpus()
+                    .iter()
+                    .flat_map(|cpu| cpu.fpga().drives()[0].1),
+            )
+            .zip(emulator.cpus().iter().flat_map(|cpu| cpu.fpga().cycles()))
+            .for_each(|(((drive, duty), phase), cycle)| {
+                drive.amp = duty.duty as f32 / cycle as f32;
+                drive.phase = 2.0 * PI * phase.phase as f32 / cycle as f32;
+                drive.set_wave_number(
+                    FPGA_CLK_FREQ as f32 / cycle as f32,
+                    self.setting.viewer_setting.sound_speed,
+                );
+            });
+        self.load_modulations(emulator);
+    }
+
+    fn load_modulations(&mut self, emulator: &Emulator) {
+        self.modulations = emulator
+            .cpus()
+            .iter()
+            .map(|cpu| cpu.fpga().modulation())
+            .collect();
+        self.update_modulation(self.modulations.first().cloned().unwrap_or_default());
+    }
+
+    fn load_fpga_flags(&mut self, emulator: &Emulator) {
+        self.is_legacy_mode = emulator.cpu(0).fpga().is_legacy_mode();
+        self.is_stm_mode = emulator.cpu(0).fpga().is_stm_mode();
+        self.is_gain_stm_mode = emulator.cpu(0).fpga().is_stm_gain_mode();
+        self.is_force_fan = emulator.cpu(0).fpga().is_force_fan();
+
+        self.stm_freq_div = emulator.cpu(0).fpga().stm_frequency_division();
+        self.silencer_cycle = emulator.cpu(0).fpga().silencer_cycle();
+        self.silencer_step = emulator.cpu(0).fpga().silencer_step();
+        self.point_stm_sound_speed = emulator.cpu(0).fpga().sound_speed();
+    }
+
+    fn update_static_mod(&mut self) {
+        if !self.modulation.0.is_empty() {
+            let v = self.modulation.0[0];
+            if self.modulation.0.iter().all(|&m| m == v) {
+                self.static_mod = v as f32 / 255.0;
+            } else {
+                self.static_mod = 1.0;
+            }
+        } else {
+            self.static_mod = 1.0;
+        }
+    }
+
+    fn playground_tab(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut changed = false;
+        if ui.checkbox("enable playground", &mut self.playground.enabled) {
+            if !self.playground.enabled {
+                self.update_drive(self.stm_idx as usize);
+            }
+            changed = true;
+        }
+        if !self.playground.enabled {
+            ui.text("Drives from the client are shown while the playground is disabled");
+            return if changed {
+                UpdateFlag::UPDATE_SOURCE_DRIVE
+            } else {
+                UpdateFlag::empty()
+            };
+        }
+
+        let pg = &mut self.playground;
+        let names: Vec<&str> = Pattern::ALL.iter().map(|p| p.name()).collect();
+        let mut idx = Pattern::ALL
+            .iter()
+            .position(|&p| p == pg.pattern)
+            .unwrap_or(0);
+        if ui.combo_simple_string("pattern", &mut idx, &names) {
+            pg.pattern = Pattern::ALL[idx];
+            changed = true;
+        }
+        if pg.pattern != Pattern::Null {
+            changed |= Slider::new("amplitude", 0.0, 1.0).build(ui, &mut pg.amp);
+        }
+
+        ui.separator();
+        match pg.pattern {
+            Pattern::Focus => {
+                changed |= Drag::new("focus").build_array(ui, &mut pg.focus);
+            }
+            Pattern::MultiFocus => {
+                let mut remove = None;
+                for (i, (pos, amp)) in pg.foci.iter_mut().enumerate() {
+                    changed |= Drag::new(&format!("focus {}", i)).build_array(ui, pos);
+                    changed |= Slider::new(&format!("amp {}", i), 0.0, 1.0).build(ui, amp);
+                    ui.same_line();
+                    if ui.small_button(&format!("remove##focus {}", i)) {
+                        remove = Some(i);
+                    }
+                }
+                if let Some(i) = remove {
+                    pg.foci.remove(i);
+                    changed = true;
+                }
+                if ui.small_button("add focus") {
+                    let pos = pg.foci.last().map_or(pg.focus, |f| f.0);
+                    pg.foci.push((pos, 1.0));
+                    changed = true;
+                }
+            }
+            Pattern::Bessel => {
+                changed |= Drag::new("apex").build_array(ui, &mut pg.bessel_apex);
+                changed |= Drag::new("direction")
+                    .speed(0.01)
+                    .build_array(ui, &mut pg.bessel_dir);
+                changed |= AngleSlider::new("theta")
+                    .range_degrees(0.0, 90.0)
+                    .build(ui, &mut pg.bessel_theta);
+            }
+            Pattern::PlaneWave => {
+                changed |= Drag::new("direction")
+                    .speed(0.01)
+                    .build_array(ui, &mut pg.plane_dir);
+            }
+            Pattern::Null => {}
+            Pattern::Uniform => {
+                changed |= AngleSlider::new("phase")
+                    .range_degrees(0.0, 360.0)
+                    .build(ui, &mut pg.uniform_phase);
+            }
+        }
+
+        if changed {
+            UpdateFlag::UPDATE_SOURCE_DRIVE
+        } else {
+            UpdateFlag::empty()
+        }
+    }
+
+    fn apply_transducer_faults(&mut self, faults: &[DeviceFault]) {
+        for (dev, fault) in faults.iter().enumerate() {
+            let offset = dev * NUM_TRANS_IN_UNIT;
+            for (&i, &(amp, phase)) in &fault.stuck {
+                if let Some(drive) = self.sources.drives_mut().nth(offset + i) {
+                    drive.amp = amp;
+                    drive.phase = phase;
+                }
+            }
+            for &i in &fault.dead {
+                if let Some(drive) = self.sources.drives_mut().nth(offset + i) {
+                    drive.amp = 0.0;
+                }
+            }
+        }
+    }
+
+    fn fault_editor(&mut self, ui: &Ui, autd_server: &mut AUTDServer) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        if self.dev_num == 0 {
+            ui.text("No device");
+            return update_flag;
+        }
+        self.fault_device = self.fault_device.min(self.dev_num - 1);
+        let devices: Vec<String> = (0..self.dev_num).map(|i| format!("Device {}", i)).collect();
+        let devices: Vec<&str> = devices.iter().map(|s| s.as_str()).collect();
+        ui.combo_simple_string("device##fault", &mut self.fault_device, &devices);
+
+        let dev = self.fault_device;
+        let fault = match autd_server.faults_mut().get_mut(dev) {
+            Some(f) => f,
+            None => return update_flag,
+        };
+        ui.checkbox("offline", &mut fault.offline);
+        ui.checkbox("ignore drive updates", &mut fault.ignore_drive);
+        ui.checkbox("stale msg_id", &mut fault.stale_msg_id);
+        ui.text(format!("rx: ack {}, msg_id {}", fault.rx[0], fault.rx[1]));
+
+        ui.separator();
+        Slider::new("transducer", 0, NUM_TRANS_IN_UNIT as i32 - 1).build(ui, &mut self.fault_trans);
+        let trans = self.fault_trans as usize;
+        if ui.small_button("dead") {
+            fault.stuck.remove(&trans);
+            fault.dead.insert(trans);
+            update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
+        }
+        ui.same_line();
+        if ui.small_button("stuck") {
+            if let Some(drive) = self.sources.drives().nth(dev * NUM_TRANS_IN_UNIT + trans) {
+                fault.dead.remove(&trans);
+                fault.stuck.insert(trans, (drive.amp, drive.phase));
+            }
+        }
+        ui.same_line();
+        if ui.small_button("restore") {
+            fault.dead.remove(&trans);
+            fault.stuck.remove(&trans);
+            self.update_drive(self.stm_idx as usize);
+            update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
+        }
+        ui.same_line();
+        if ui.small_button("restore all") {
+            fault.dead.clear();
+            fault.stuck.clear();
+            self.update_drive(self.stm_idx as usize);
+            update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
+        }
+        if !fault.dead.is_empty() {
+            ui.text_wrapped(format!("dead: {:?}", fault.dead));
+        }
+        if !fault.stuck.is_empty() {
+            ui.text_wrapped(format!(
+                "stuck: {:?}",
+                fault.stuck.keys().collect::<Vec<_>>()
+            ));
+        }
+        update_flag
+    }
+
+    fn power_cycle(&mut self, autd_server: &mut AUTDServer, keep_geometry: bool) -> UpdateFlag {
+        autd_server.power_cycle(keep_geometry);
+        let update_flag = self.on_power_cycle(autd_server.emulator(), keep_geometry);
+        self.log(Record::info(if keep_geometry {
+            "power cycle (geometry kept)"
+        } else {
+            "power cycle (geometry cleared)"
+        }));
+        update_flag
+    }
+
+    // refreshes the viewer state after the devices are powered on again
+    fn on_power_cycle(&mut self, emulator: &Emulator, keep_geometry: bool) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::UPDATE_SOURCE_DRIVE;
+        if !keep_geometry {
+            self.sources.clear();
+            self.axis.clear();
+            self.layouts.clear();
+            self.dev_num = 0;
+            update_flag |= UpdateFlag::INIT_SOURCE;
+            update_flag |= UpdateFlag::INIT_AXIS;
+        }
+        self.history.clear();
+        self.history_cursor = None;
+        self.warnings.clear();
+        self.stm_idx = 0;
+        self.drives = emulator
+            .cpus()
+            .iter()
+            .map(|cpu| cpu.fpga().drives())
+            .collect();
+        self.cycles = emulator
+            .cpus()
+            .iter()
+            .flat_map(|cpu| cpu.fpga().cycles())
+            .collect();
+        self.fpga_states = FpgaState::collect(emulator);
+        if emulator.cpus().is_empty() {
+            self.load_modulations(emulator);
+        } else {
+            self.load_initial_drives(emulator);
+            self.load_fpga_flags(emulator);
+        }
+        self.update_static_mod();
+        update_flag
+    }
+
+    fn reboot_device(&mut self, autd_server: &mut AUTDServer, idx: usize) -> UpdateFlag {
+        autd_server.reboot_device(idx);
+        let update_flag = self.on_reboot(autd_server.emulator(), idx);
+        self.log(Record::warn("rebooted").device(Some(idx)));
+        update_flag
+    }
+
+    // refreshes the viewer state after a device is rebooted
+    fn on_reboot(&mut self, emulator: &Emulator, idx: usize) -> UpdateFlag {
+        if let (Some(m), Some(cpu)) = (self.modulations.get_mut(idx), emulator.cpus().get(idx)) {
+            *m = cpu.fpga().modulation();
+        }
+        if idx == 0 && !emulator.cpus().is_empty() {
+            // modulation and the flags shown are those of the first device
+            self.update_modulation(emulator.fpga(0).modulation());
+            self.update_static_mod();
+            self.load_fpga_flags(emulator);
+        }
+        self.drives = emulator
+            .cpus()
+            .iter()
+            .map(|cpu| cpu.fpga().drives())
+            .collect();
+        self.cycles = emulator
+            .cpus()
+            .iter()
+            .flat_map(|cpu| cpu.fpga().cycles())
+            .collect();
+        self.fpga_states = FpgaState::collect(emulator);
+        self.update_drive(self.stm_idx as usize);
+        self.check_consistency(emulator);
+        UpdateFlag::UPDATE_SOURCE_DRIVE
+    }
+
+    fn update_view(&mut self, renderer: &mut Renderer, update_flag: UpdateFlag) {
+        self.trans_viewer.update(
+            renderer,
+            &self.view_projection,
+            &self.setting.viewer_setting,
+            &self.sources,
+            update_flag,
+        );
+        self.dir_viewer.update(
+            renderer,
+            &self.view_projection,
+            &self.setting.viewer_setting,
+            &self.axis,
+            update_flag,
+        );
+        let gizmo_flag = if update_flag.contains(UpdateFlag::UPDATE_SOURCE_DRIVE) {
+            update_flag | UpdateFlag::INIT_AXIS
+        } else {
+            update_flag
+        };
+        self.gizmo_viewer.update(
+            renderer,
+            &self.view_projection,
+            &self.setting.viewer_setting,
+            &self.focus_gizmo.axes(&self.playground.focus_points()),
+            gizmo_flag,
+        );
+        self.slice_viewer.update(
+            renderer,
+            &self.view_projection,
+            &self.setting.viewer_setting,
+            update_flag,
+        );
+        self.field_compute_pipeline.update(
+            &self.sources,
+            update_flag,
+            &self.setting.viewer_setting,
+        );
+        // the other slices have their own position, size and color
+        let shared_flag = update_flag
+            & (UpdateFlag::INIT_SOURCE
+                | UpdateFlag::UPDATE_SOURCE_DRIVE
+                | UpdateFlag::UPDATE_SOURCE_FLAG
+                | UpdateFlag::UPDATE_CAMERA_POS);
+        for slice in self.slices.iter_mut() {
+            slice.update(
+                renderer,
+                &self.view_projection,
+                &self.setting.viewer_setting,
+                &self.sources,
+                shared_flag,
+            );
+        }
+        if let Err(e) = self.volume_viewer.update(
+            renderer,
+            &self.view_projection,
+            &self.setting.viewer_setting,
+            &self.setting.volume,
+            update_flag,
+        ) {
+            self.log(Record::error(format!(
+                "failed to allocate the volume: {}",
+                e
+            )));
+        }
+        let volume_setting = self.volume_compute_setting();
+        self.volume_pipeline
+            .update(&self.sources, update_flag, &volume_setting);
+        self.volume_pending |= update_flag.intersects(
+            UpdateFlag::INIT_SOURCE
+                | UpdateFlag::UPDATE_COLOR_MAP
+                | UpdateFlag::UPDATE_SOURCE_DRIVE
+                | UpdateFlag::UPDATE_SOURCE_FLAG
+                | UpdateFlag::UPDATE_VOLUME,
+        );
+        if update_flag.contains(UpdateFlag::UPDATE_COLOR_MAP) {
+            self.colorbar_colors = self
+                .setting
+                .viewer_setting
+                .color_map
+                .sample(&self.custom_color_map, COLORBAR_SIZE);
+        }
+    }
+
+    fn update_gizmo(&mut self, io: &Io) -> UpdateFlag {
+        let points = self.playground.focus_points();
+        let mouse_down =
+            io.mouse_down[0] && (self.focus_gizmo.is_dragging() || !io.want_capture_mouse);
+        match self.focus_gizmo.update(
+            &points,
+            io.mouse_pos,
+            mouse_down,
+            io.display_size,
+            &self.view_projection,
+        ) {
+            Some((idx, pos)) if pos != points[idx] => {
+                self.playground.set_focus_point(idx, pos);
+                UpdateFlag::UPDATE_SOURCE_DRIVE
+            }
+            _ => UpdateFlag::empty(),
+        }
+    }
+
+    fn update_camera(&mut self, renderer: &mut Renderer, io: &Io) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+
+        let mouse_wheel = io.mouse_wheel;
+        if !io.want_capture_mouse && mouse_wheel != 0.0 {
+            let trans = vecmath::vec3_scale(
+                renderer.camera.forward,
+                -mouse_wheel * self.setting.viewer_setting.camera_move_speed,
+            );
+            self.setting.viewer_setting.camera_pos =
+                vecmath::vec3_add(self.setting.viewer_setting.camera_pos, trans);
+            renderer.camera.position = self.setting.viewer_setting.camera_pos;
+            self.view_projection = renderer.get_view_projection(&self.setting.viewer_setting);
+            update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+        }
+        let mouse_delta = io.mouse_delta;
+        if !io.want_capture_mouse
+            && !self.focus_gizmo.is_dragging()
+            && io.mouse_down[0]
+            && !vecmath_util::is_zero(&mouse_delta)
+        {
+            if io.key_shift {
+                let mouse_delta = vecmath::vec2_scale(
+                    mouse_delta,
+                    self.setting.viewer_setting.camera_move_speed / 3000.0,
+                );
+                let trans_x = vecmath::vec3_scale(renderer.camera.right, mouse_delta[0]);
+                let trans_y = vecmath::vec3_scale(renderer.camera.up, -mouse_delta[1]);
+                let to =
+                    vecmath::vec3_add(vecmath::vec3_add(trans_x, trans_y), renderer.camera.forward);
+                let rot = vecmath_util::quaternion_to(renderer.camera.forward, to);
+
+                renderer.camera.forward = quaternion::rotate_vector(rot, renderer.camera.forward);
+                renderer.camera.up = quaternion::rotate_vector(rot, renderer.camera.up);
+                renderer.camera.right = quaternion::rotate_vector(rot, renderer.camera.right);
+                let rotm = [
+                    renderer.camera.right,
+                    renderer.camera.up,
+                    renderer.camera.forward,
+                ];
+                self.setting.viewer_setting.camera_angle =
+                    camera_helper::rot_mat_to_euler_angles(&rotm);
+            } else {
+                let mouse_delta = vecmath::vec2_scale(
+                    mouse_delta,
+                    self.setting.viewer_setting.camera_move_speed / 10.0,
+                );
+                let trans_x = vecmath::vec3_scale(renderer.camera.right, -mouse_delta[0]);
+                let trans_y = vecmath::vec3_scale(renderer.camera.up, mouse_delta[1]);
+                let trans = vecmath::vec3_add(trans_x, trans_y);
+                self.setting.viewer_setting.camera_pos =
+                    vecmath::vec3_add(self.setting.viewer_setting.camera_pos, trans);
+                renderer.camera.position = self.setting.viewer_setting.camera_pos;
+            }
+            self.view_projection = renderer.get_view_projection(&self.setting.viewer_setting);
+            update_flag |= UpdateFlag::UPDATE_CAMERA_POS;
+        }
+
+        update_flag
+    }
+
+    fn update_ui(
+        &mut self,
+        ui: &Ui,
+        renderer: &mut Renderer,
+        autd_server: &mut AUTDServer,
+    ) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+        self.save_image = false;
+        Window::new("Controller").build(ui, || {
+            TabBar::new("Settings").build(ui, || {
+                TabItem::new("Slice").build(ui, || {
+                    ui.text("Slice size");
+                    if Slider::new("Slice width", 1, 1000)
+                        .build(ui, &mut self.setting.viewer_setting.slice_width)
+                    {
+                        update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
+                    }
+                    if Slider::new("Slice heigh", 1, 1000)
+                        .build(ui, &mut self.setting.viewer_setting.slice_height)
+                    {
+                        update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
+                    }
+                    if Slider::new("Pixel size", 1, 8)
+                        .build(ui, &mut self.setting.viewer_setting.slice_pixel_size)
+                    {
+                        update_flag |= UpdateFlag::UPDATE_SLICE_SIZE;
+                    }
+
+                    ui.separator();
+                    ui.text("Slice position");
+                    if Drag::new("Slice X").build(ui, &mut self.setting.viewer_setting.slice_pos[0])
+                    {
+                        self.slice_viewer
+                            .move_to(self.setting.viewer_setting.slice_pos);
+                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+                    }
+                    if Drag::new("Slice Y").build(ui, &mut self.setting.viewer_setting.slice_pos[1])
+                    {
+                        self.slice_viewer
+                            .move_to(self.setting.viewer_setting.slice_pos);
+                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+                    }
+                    if Drag::new("Slice Z").build(ui, &mut self.setting.viewer_setting.slice_pos[2])
+                    {
+                        self.slice_viewer
+                            .move_to(self.setting.viewer_setting.slice_pos);
+                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+                    }
+
+                    ui.separator();
+                    ui.text("Slice Rotation");
+                    if AngleSlider::new("Slice RX")
+                        .range_degrees(0.0, 360.0)
+                        .build(ui, &mut self.setting.viewer_setting.slice_angle[0])
+                    {
+                        self.slice_viewer
+                            .rotate_to(self.setting.viewer_setting.slice_angle);
+                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+                    }
+                    if AngleSlider::new("Slice RY")
+                        .range_degrees(0.0, 360.0)
+                        .build(ui, &mut self.setting.viewer_setting.slice_angle[1])
+                    {
+                        self.slice_viewer
+                            .rotate_to(self.setting.viewer_setting.slice_angle);
+                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+                    }
+                    if AngleSlider::new("Slice RZ")
+                        .range_degrees(0.0, 360.0)
+                        .build(ui, &mut self.setting.viewer_setting.slice_angle[2])
+                    {
+                        self.slice_viewer
+                            .rotate_to(self.setting.viewer_setting.slice_angle);
+                        update_flag |= UpdateFlag::UPDATE_SLICE_POS;
+                    }
+
+                    ui.separator();
+                    ui.text("Slice color setting");
+                    let names: Vec<&str> = SliceQuantity::ALL.iter().map(|q| q.name()).collect();
+                    let mut idx = SliceQuantity::ALL
+                        .iter()
+                        .position(|&q| q == self.setting.viewer_setting.slice_quantity)
+                        .unwrap_or(0);
+                    if ui.combo_simple_string("Quantity", &mut idx, &names) {
+                        self.setting.viewer_setting.slice_quantity = SliceQuantity::ALL[idx];
+                        update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+                    }
+                    if Drag::new("Color scale")
                         .speed(0.1)
                         .range(0.0, f32::INFINITY)
                         .build(ui, &mut self.setting.viewer_setting.color_scale)
@@ -615,6 +2116,24 @@ impl App {
                     {
                         update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
                     }
+                    if ui.checkbox("dB", &mut self.setting.viewer_setting.db_mode) {
+                        update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+                    }
+                    if self.setting.viewer_setting.db_mode {
+                        ui.same_line();
+                        if Drag::new("Dynamic range [dB]")
+                            .range(1.0, 200.0)
+                            .speed(0.5)
+                            .build(ui, &mut self.setting.viewer_setting.db_range)
+                        {
+                            update_flag |= UpdateFlag::UPDATE_COLOR_MAP;
+                        }
+                        ui.text_disabled("0 dB is the color scale");
+                    }
+                    update_flag |= self.color_scale_setting(ui);
+                    update_flag |= self.color_map_setting(ui);
+                    self.contour_setting(ui);
+                    self.profile_setting(ui);
 
                     ui.separator();
                     if ui.small_button("xy") {
@@ -637,6 +2156,14 @@ impl App {
                             .rotate_to(self.setting.viewer_setting.slice_angle);
                         update_flag |= UpdateFlag::UPDATE_SLICE_POS;
                     }
+
+                    ui.separator();
+                    if CollapsingHeader::new("Additional slices").build(ui) {
+                        self.slices_setting(ui, renderer);
+                    }
+                });
+                TabItem::new("Volume").build(ui, || {
+                    update_flag |= self.volume_setting(ui);
                 });
                 TabItem::new("Camera").build(ui, || {
                     ui.text("Camera pos");
@@ -755,6 +2282,10 @@ impl App {
                     ui.text("Device index/show/enable/axis");
                     for i in 0..self.dev_num {
                         ui.text(format!("Device {}", i));
+                        if let Some(fault) = autd_server.faults().get(i).filter(|f| f.is_faulty()) {
+                            ui.same_line();
+                            ui.text_colored([1.0, 0.3, 0.3, 1.0], format!("[{}]", fault.summary()));
+                        }
                         ui.same_line();
                         if ui.checkbox(&format!("show##{}", i), &mut self.setting.show[i]) {
                             for v in self
@@ -784,7 +2315,19 @@ impl App {
                             self.axis[i].show = self.setting.show_axis[i];
                             update_flag |= UpdateFlag::UPDATE_AXIS_FLAG;
                         }
+                        ui.same_line();
+                        if ui.small_button(&format!("reboot##{}", i)) {
+                            update_flag |= self.reboot_device(autd_server, i);
+                        }
+                    }
+                    if CollapsingHeader::new("Faults").build(ui) {
+                        update_flag |= self.fault_editor(ui, autd_server);
                     }
+                    if ui.small_button("power cycle") {
+                        update_flag |= self.power_cycle(autd_server, self.keep_geometry);
+                    }
+                    ui.same_line();
+                    ui.checkbox("keep geometry", &mut self.keep_geometry);
                     if Drag::new("Axis length")
                         .speed(1.0)
                         .range(0.0, f32::INFINITY)
@@ -800,11 +2343,20 @@ impl App {
                         update_flag |= UpdateFlag::UPDATE_AXIS_SIZE;
                     }
 
+                    ui.separator();
+                    InputText::new(ui, "geometry file", &mut self.setting.geometry_file).build();
+                    if ui.small_button("load geometry") {
+                        update_flag |= self.load_geometry_file(autd_server);
+                    }
+
                     ui.separator();
                     ColorPicker::new("Background", &mut self.setting.viewer_setting.background)
                         .alpha(true)
                         .build(ui);
                 });
+                TabItem::new("Playground").build(ui, || {
+                    update_flag |= self.playground_tab(ui);
+                });
                 TabItem::new("Info").build(ui, || {
                     ui.text(format!("fps: {:.1}", self.fps));
 
@@ -844,6 +2396,10 @@ impl App {
                         ui.text(format!("mod[{}]: {}", idx, m.0[idx]));
                     }
 
+                    if ui.small_button("open modulation window") {
+                        self.show_mod_window = true;
+                    }
+
                     if ui.radio_button_bool("show mod plot", self.setting.show_mod_plot) {
                         self.setting.show_mod_plot = !self.setting.show_mod_plot;
                     }
@@ -921,20 +2477,34 @@ impl App {
                     ui.checkbox("STM MODE", &mut value);
                     let mut value = self.is_gain_stm_mode;
                     ui.checkbox("STM GAIN MODE", &mut value);
-                });
-                TabItem::new("Log").build(ui, || {
-                    if ui.radio_button_bool("enable", self.setting.log_enable) {
-                        self.setting.log_enable = !self.setting.log_enable;
-                    }
-                    if ui.small_button("clear") {
-                        self.log_clear();
+
+                    ui.separator();
+                    ui.text("FPGA state per device");
+                    self.fpga_state_table(ui);
+                });
+                TabItem::new("Warnings").build(ui, || {
+                    if self.warnings.is_empty() {
+                        ui.text("No inconsistency found");
                     }
-                    if self.setting.log_enable {
-                        Slider::new("Max", 0, 1000).build(ui, &mut self.setting.log_max);
-
-                        ui.text(self.get_log_txt());
+                    for warning in &self.warnings {
+                        ui.text_colored([1.0, 0.8, 0.2, 1.0], format!("{}", warning));
                     }
                 });
+                TabItem::new("History").build(ui, || {
+                    update_flag |= self.history_tab(ui);
+                });
+                TabItem::new("Step").build(ui, || {
+                    self.step_tab(ui, autd_server);
+                });
+                TabItem::new("Packets").build(ui, || {
+                    self.packet_inspector(ui, autd_server);
+                });
+                TabItem::new("Traffic").build(ui, || {
+                    self.traffic_tab(ui, autd_server);
+                });
+                TabItem::new("Log").build(ui, || {
+                    self.log_tab(ui);
+                });
             });
 
             ui.separator();
@@ -944,6 +2514,35 @@ impl App {
                 self.save_image = true;
             }
 
+            ui.separator();
+            ui.text("Export slice field");
+            InputText::new(ui, "path to field", &mut self.setting.field_export_path).build();
+            let names: Vec<&str> = Quantity::ALL.iter().map(|q| q.name()).collect();
+            let mut idx = Quantity::ALL
+                .iter()
+                .position(|&q| q == self.setting.field_export_quantity)
+                .unwrap_or(0);
+            if ui.combo_simple_string("quantity", &mut idx, &names) {
+                self.setting.field_export_quantity = Quantity::ALL[idx];
+            }
+            if ui.small_button("export npy") {
+                self.export_field(None, false);
+            }
+            ui.same_line();
+            if ui.small_button("export csv") {
+                self.export_field(None, true);
+            }
+
+            ui.separator();
+            InputText::new(ui, "path to scene", &mut self.setting.scene_path).build();
+            if ui.small_button("save scene") {
+                self.save_scene();
+            }
+            ui.same_line();
+            if ui.small_button("load scene") {
+                update_flag |= self.load_scene(renderer, autd_server);
+            }
+
             ui.separator();
             InputText::new(ui, "path to recorded images", &mut self.setting.record_path).build();
             if ui.small_button(if self.recording {
@@ -997,17 +2596,24 @@ impl App {
 
             ui.same_line();
             if ui.small_button("reset") {
-                let show = self.setting.show.to_owned();
-                let enable = self.setting.enable.to_owned();
-                let show_axis = self.setting.show_axis.to_owned();
-                self.setting = Setting {
-                    show,
-                    enable,
-                    show_axis,
-                    ..Setting::load("setting.json")
-                };
-                self.reset(renderer);
-                update_flag = UpdateFlag::all();
+                match Setting::load("setting.json") {
+                    Ok(setting) => {
+                        let show = self.setting.show.to_owned();
+                        let enable = self.setting.enable.to_owned();
+                        let show_axis = self.setting.show_axis.to_owned();
+                        self.setting = Setting {
+                            show,
+                            enable,
+                            show_axis,
+                            ..setting
+                        };
+                        self.reset(renderer);
+                        update_flag = UpdateFlag::all();
+                    }
+                    Err(e) => {
+                        self.log(Record::error(format!("failed to load setting.json: {}", e)))
+                    }
+                }
             }
 
             ui.same_line();
@@ -1037,9 +2643,700 @@ impl App {
             }
         });
 
+        self.modulation_window(ui);
+        let hovered = self.transducer_picker(ui);
+        self.slice_readout(ui, !hovered);
+        if self.setting.show_contours {
+            self.draw_contours(ui);
+        }
+        if self.setting.show_profile {
+            self.profile_window(ui);
+        }
+        if self.setting.show_colorbar {
+            let (ticks, unit) = self.colorbar_ticks();
+            colorbar::draw(ui, &self.colorbar_colors, &ticks, unit);
+        }
+
         update_flag
     }
 
+    fn transducer_picker(&mut self, ui: &Ui) -> bool {
+        let io = ui.io();
+        let mut hovered = false;
+        if !io.want_capture_mouse && !self.focus_gizmo.is_dragging() {
+            let (origin, dir) =
+                camera_helper::screen_to_ray(io.mouse_pos, io.display_size, &self.view_projection);
+            if let Some(idx) =
+                TransViewer::pick(&self.sources, &self.setting.viewer_setting, origin, dir)
+            {
+                ui.tooltip(|| {
+                    self.transducer_info(ui, idx);
+                    ui.text_disabled("right click to pin");
+                });
+                if ui.is_mouse_clicked(MouseButton::Right) {
+                    self.pinned_trans = Some(idx);
+                }
+                hovered = true;
+            }
+        }
+
+        let idx = match self.pinned_trans {
+            Some(idx) if idx < self.sources.len() => idx,
+            _ => {
+                self.pinned_trans = None;
+                return hovered;
+            }
+        };
+        let mut opened = true;
+        Window::new("Transducer")
+            .opened(&mut opened)
+            .size([280.0, 200.0], Condition::FirstUseEver)
+            .build(ui, || self.transducer_info(ui, idx));
+        if !opened {
+            self.pinned_trans = None;
+        }
+        hovered
+    }
+
+    fn slice_readout(&mut self, ui: &Ui, enable: bool) {
+        let io = ui.io();
+        if enable && !io.want_capture_mouse && !self.focus_gizmo.is_dragging() {
+            let (origin, dir) =
+                camera_helper::screen_to_ray(io.mouse_pos, io.display_size, &self.view_projection);
+            if let Some(p) = self
+                .slice_viewer
+                .intersect(&self.setting.viewer_setting, origin, dir)
+            {
+                ui.tooltip(|| {
+                    ui.text(Self::field_info(p, self.slice_field_at(p)));
+                    ui.text_disabled("right click to pin");
+                });
+                if ui.is_mouse_clicked(MouseButton::Right) {
+                    self.slice_markers.push(p);
+                }
+            }
+        }
+
+        let draw_list = ui.get_foreground_draw_list();
+        for (i, &p) in self.slice_markers.iter().enumerate() {
+            if let Some(s) =
+                camera_helper::world_to_screen(p, io.display_size, &self.view_projection)
+            {
+                draw_list
+                    .add_circle(s, 4.0, [1.0, 1.0, 1.0, 1.0])
+                    .thickness(2.0)
+                    .build();
+                draw_list.add_text(
+                    [s[0] + 6.0, s[1] - 6.0],
+                    [1.0, 1.0, 1.0, 1.0],
+                    i.to_string(),
+                );
+            }
+        }
+        drop(draw_list);
+
+        if self.slice_markers.is_empty() {
+            return;
+        }
+        let mut opened = true;
+        let mut remove = None;
+        Window::new("Slice markers")
+            .opened(&mut opened)
+            .size([360.0, 200.0], Condition::FirstUseEver)
+            .build(ui, || {
+                for (i, &p) in self.slice_markers.iter().enumerate() {
+                    if ui.small_button(&format!("remove##marker{}", i)) {
+                        remove = Some(i);
+                    }
+                    ui.same_line();
+                    ui.text(format!(
+                        "{}: {}",
+                        i,
+                        Self::field_info(p, self.slice_field_at(p))
+                    ));
+                }
+            });
+        if let Some(i) = remove {
+            self.slice_markers.remove(i);
+        }
+        if !opened {
+            self.slice_markers.clear();
+        }
+    }
+
+    fn slice_field_at(&self, p: Vector3) -> Option<(f32, f32)> {
+        self.slice_viewer
+            .field_at(&self.setting.viewer_setting, p)
+            .map(|[re, im]| (re.hypot(im), im.atan2(re)))
+    }
+
+    fn field_info(p: Vector3, field: Option<(f32, f32)>) -> String {
+        let pos = format!("({:.2}, {:.2}, {:.2}) [mm]", p[0], p[1], p[2]);
+        match field {
+            Some((amp, phase)) => {
+                format!("{}\nAmplitude: {:.4}, Phase: {:.3} [rad]", pos, amp, phase)
+            }
+            None => format!("{}\nout of slice", pos),
+        }
+    }
+
+    fn transducer_info(&self, ui: &Ui, idx: usize) {
+        ui.text(format!(
+            "Device {}, Transducer {} (#{})",
+            idx / NUM_TRANS_IN_UNIT,
+            idx % NUM_TRANS_IN_UNIT,
+            idx
+        ));
+        if let Some(pos) = self.sources.positions().nth(idx) {
+            ui.text(format!(
+                "Position: ({:.2}, {:.2}, {:.2}) [mm]",
+                pos[0], pos[1], pos[2]
+            ));
+        }
+        match self.raw_drive(idx) {
+            Some((duty, phase, cycle)) => {
+                ui.text(format!(
+                    "Duty: {}, Phase: {}, Cycle: {}",
+                    duty, phase, cycle
+                ));
+                ui.text(format!(
+                    "Frequency: {:.1} [Hz]",
+                    FPGA_CLK_FREQ as f64 / cycle as f64
+                ));
+            }
+            None => ui.text("No drive data"),
+        }
+        if let Some(drive) = self.sources.drives().nth(idx) {
+            ui.text(format!(
+                "Amplitude: {:.3}, Phase: {:.3} [rad]",
+                drive.amp, drive.phase
+            ));
+            ui.text(format!("Enable: {}", drive.enable > 0.0));
+        }
+        if self.playground.enabled {
+            ui.text_colored(
+                [1.0, 1.0, 0.0, 1.0],
+                "Amplitude/phase are set by the playground",
+            );
+        }
+    }
+
+    // duty, phase and cycle as sent by the client, at the STM index being displayed
+    fn raw_drive(&self, idx: usize) -> Option<(u16, u16, u16)> {
+        let (drives, cycles) = match self.history_cursor.and_then(|id| self.history.find(id)) {
+            Some(s) => (&s.drives, &s.cycles),
+            None => (&self.drives, &self.cycles),
+        };
+        let stm_idx = self.stm_idx as usize;
+        let stm_idx = if drives.iter().all(|d| stm_idx < d.len()) {
+            stm_idx
+        } else {
+            0
+        };
+        let (duty, phase) = drives.get(idx / NUM_TRANS_IN_UNIT)?.get(stm_idx)?;
+        let local = idx % NUM_TRANS_IN_UNIT;
+        Some((duty[local].duty, phase[local].phase, *cycles.get(idx)?))
+    }
+
+    fn modulation_window(&mut self, ui: &Ui) {
+        if !self.show_mod_window {
+            return;
+        }
+
+        let mut opened = true;
+        let mut export_wav = false;
+        Window::new("Modulation")
+            .opened(&mut opened)
+            .size([480.0, 480.0], Condition::FirstUseEver)
+            .build(ui, || {
+                let a = &self.mod_analysis;
+                ui.text(format!("Size: {}", a.envelope.len()));
+                ui.text(format!("Sampling frequency: {} [Hz]", a.sampling_freq));
+                ui.text(format!("RMS: {:.4}", a.rms));
+                ui.text(format!("Peak: {:.4}", a.peak));
+                ui.text(format!("Mean: {:.4}", a.mean));
+                if a.envelope.is_empty() {
+                    return;
+                }
+
+                ui.separator();
+                ui.text("Envelope");
+                let n = a.envelope.len();
+                let count = ((n as f32 / self.mod_zoom).ceil() as usize).clamp(n.min(2), n);
+                let start = ((n - count) as f32 * self.mod_offset) as usize;
+                let width = ui.content_region_avail()[0];
+                PlotLines::new(ui, "##envelope", &a.envelope[start..start + count])
+                    .graph_size([width, 120.0])
+                    .scale_min(0.0)
+                    .scale_max(1.0)
+                    .build();
+                if ui.is_item_hovered() {
+                    let io = ui.io();
+                    if io.mouse_wheel != 0.0 {
+                        self.mod_zoom = (self.mod_zoom * (1.0 + 0.1 * io.mouse_wheel))
+                            .clamp(1.0, (n as f32 / 2.0).max(1.0));
+                    }
+                    if ui.is_mouse_dragging(MouseButton::Left) && count < n {
+                        let d = io.mouse_delta[0] / width * count as f32 / (n - count) as f32;
+                        self.mod_offset = (self.mod_offset - d).clamp(0.0, 1.0);
+                    }
+                }
+                if a.sampling_freq > 0.0 {
+                    let period = 1000.0 / a.sampling_freq;
+                    ui.text(format!(
+                        "Range: {:.3} - {:.3} [ms]",
+                        start as f64 * period,
+                        (start + count) as f64 * period
+                    ));
+                }
+                Slider::new("Zoom", 1.0, (n as f32 / 2.0).max(1.0)).build(ui, &mut self.mod_zoom);
+                Slider::new("Offset", 0.0, 1.0).build(ui, &mut self.mod_offset);
+
+                ui.separator();
+                ui.text("Spectrum");
+                let nyquist = (a.sampling_freq / 2.0) as f32;
+                let bins = ((self.mod_spectrum_max as f64 / a.freq_resolution) as usize + 1)
+                    .clamp(2.min(a.spectrum.len()), a.spectrum.len());
+                PlotLines::new(ui, "##spectrum", &a.spectrum[0..bins])
+                    .graph_size([width, 120.0])
+                    .scale_min(0.0)
+                    .build();
+                Slider::new("Max frequency [Hz]", 0.0, nyquist)
+                    .build(ui, &mut self.mod_spectrum_max);
+                if let Some((freq, mag)) = a.peak_frequency() {
+                    ui.text(format!("Peak: {:.2} [Hz] (magnitude {:.4})", freq, mag));
+                }
+                ui.text(format!("Resolution: {:.3} [Hz]", a.freq_resolution));
+
+                ui.separator();
+                InputText::new(ui, "path to wav", &mut self.setting.mod_wav_path).build();
+                Drag::new("duration [s]")
+                    .range(0.0, f32::INFINITY)
+                    .speed(0.1)
+                    .build(ui, &mut self.setting.mod_wav_duration);
+                if ui.small_button("export wav") {
+                    export_wav = true;
+                }
+            });
+        self.show_mod_window = opened;
+
+        if export_wav {
+            match self
+                .mod_analysis
+                .save_wav(&self.setting.mod_wav_path, self.setting.mod_wav_duration)
+            {
+                Ok(()) => self.log(Record::info(format!(
+                    "save wav: {}",
+                    self.setting.mod_wav_path
+                ))),
+                Err(e) => self.log(Record::error(format!("failed to save wav: {}", e))),
+            }
+        }
+    }
+
+    fn packet_inspector(&mut self, ui: &Ui, autd_server: &mut AUTDServer) {
+        if Slider::new("Max", 1, 10000).build(ui, &mut self.setting.packet_max) {
+            autd_server
+                .packets_mut()
+                .set_max(self.setting.packet_max as _);
+        }
+        if ui.small_button("clear##packets") {
+            autd_server.packets_mut().clear();
+            self.selected_packet = None;
+        }
+
+        let kinds: Vec<&str> = std::iter::once("all")
+            .chain(EventKind::ALL.iter().map(|k| k.name()))
+            .collect();
+        ui.combo_simple_string("kind", &mut self.packet_kind_filter, &kinds);
+        InputText::new(ui, "cpu flag", &mut self.packet_flag_filter).build();
+
+        let packets = autd_server.packets().packets();
+        let kind_filter = match self.packet_kind_filter {
+            0 => None,
+            i => Some(EventKind::ALL[i - 1]),
+        };
+        let flag_filter = self.packet_flag_filter.to_uppercase();
+        let filtered: Vec<&Packet> = packets
+            .iter()
+            .filter(|p| kind_filter.map_or(true, |k| p.kind() == Some(k)))
+            .filter(|p| {
+                flag_filter.is_empty()
+                    || p.cpu_flag()
+                        .map_or(false, |f| format!("{:?}", f).contains(&flag_filter))
+            })
+            .collect();
+        ui.text(format!("{} / {} packets", filtered.len(), packets.len()));
+
+        ChildWindow::new("packet list")
+            .size([0.0, 160.0])
+            .border(true)
+            .build(ui, || {
+                let mut clipper = ListClipper::new(filtered.len() as i32)
+                    .items_height(ui.text_line_height_with_spacing())
+                    .begin(ui);
+                while clipper.step() {
+                    for i in clipper.display_start()..clipper.display_end() {
+                        let p = filtered[i as usize];
+                        let label = format!(
+                            "#{} {} {} bytes {}",
+                            p.seq,
+                            p.time.format("%H:%M:%S%.3f"),
+                            p.raw.len(),
+                            p.kind().map_or("invalid", |k| k.name())
+                        );
+                        if Selectable::new(&label)
+                            .selected(self.selected_packet == Some(p.seq))
+                            .build(ui)
+                        {
+                            self.selected_packet = Some(p.seq);
+                        }
+                    }
+                }
+            });
+
+        let p = match self
+            .selected_packet
+            .and_then(|seq| autd_server.packets().find(seq))
+        {
+            Some(p) => p,
+            None => return,
+        };
+
+        ui.separator();
+        ui.text(format!(
+            "#{} {}",
+            p.seq,
+            p.time.format("%Y-%m-%d %H:%M:%S%.6f")
+        ));
+        ui.text(format!("Size: {} bytes", p.raw.len()));
+        match p.header() {
+            Some(h) => {
+                ui.text(format!(
+                    "msg_id: {} ({})",
+                    h.msg_id,
+                    EventKind::from_msg_id(h.msg_id).name()
+                ));
+                ui.text(format!("CPU flag: {:?}", h.cpu_flag));
+                ui.text(format!("FPGA flag: {:?}", h.fpga_flag));
+                ui.text(format!("size: {}", h.size));
+            }
+            None => ui.text("Too short to contain a header"),
+        }
+
+        let format = p.body_format();
+        ui.text(format!("Body format: {}", format.name()));
+        for dev in 0..p.num_bodies() {
+            if !CollapsingHeader::new(&format!("Body {}", dev)).build(ui) {
+                continue;
+            }
+            ChildWindow::new(&format!("body {}", dev))
+                .size([0.0, 200.0])
+                .build(ui, || {
+                    if let Some(_table) = ui.begin_table_with_flags(
+                        &format!("body table {}", dev),
+                        4,
+                        TableFlags::BORDERS | TableFlags::ROW_BG,
+                    ) {
+                        ui.table_setup_column("Index");
+                        ui.table_setup_column("Raw");
+                        ui.table_setup_column("Duty");
+                        ui.table_setup_column("Phase");
+                        ui.table_headers_row();
+                        for (i, &v) in p.body(dev).iter().enumerate() {
+                            let (duty, phase) = match format {
+                                BodyFormat::Legacy => {
+                                    (format!("{}", v >> 8), format!("{}", v & 0xFF))
+                                }
+                                BodyFormat::Duty => (format!("{}", v), "-".to_owned()),
+                                BodyFormat::Phase => ("-".to_owned(), format!("{}", v)),
+                                BodyFormat::Raw => ("-".to_owned(), "-".to_owned()),
+                            };
+                            ui.table_next_row();
+                            ui.table_next_column();
+                            ui.text(format!("{}", i));
+                            ui.table_next_column();
+                            ui.text(format!("0x{:04x}", v));
+                            ui.table_next_column();
+                            ui.text(duty);
+                            ui.table_next_column();
+                            ui.text(phase);
+                        }
+                    }
+                });
+        }
+
+        if CollapsingHeader::new("Hex dump").build(ui) {
+            let lines = p.hex_dump();
+            ChildWindow::new("hex dump")
+                .size([0.0, 200.0])
+                .build(ui, || {
+                    let mut clipper = ListClipper::new(lines.len() as i32)
+                        .items_height(ui.text_line_height_with_spacing())
+                        .begin(ui);
+                    while clipper.step() {
+                        for i in clipper.display_start()..clipper.display_end() {
+                            ui.text(&lines[i as usize]);
+                        }
+                    }
+                });
+        }
+    }
+
+    fn step_tab(&mut self, ui: &Ui, autd_server: &mut AUTDServer) {
+        if autd_server.is_paused() {
+            ui.text_colored([1.0, 0.8, 0.2, 1.0], "Paused");
+            if ui.small_button("resume") {
+                autd_server.resume();
+            }
+            ui.same_line();
+            if ui.small_button("step") {
+                autd_server.step();
+            }
+        } else {
+            ui.text("Running");
+            if ui.small_button("pause") {
+                autd_server.pause();
+            }
+        }
+
+        let queue = autd_server.queue();
+        ui.text(format!("Queued: {}", queue.len()));
+        if let Some(p) = queue.front() {
+            ui.text(format!(
+                "Next: {} {} bytes {}",
+                p.kind().map_or("invalid", |k| k.name()),
+                p.raw.len(),
+                p.cpu_flag().map_or(String::new(), |f| format!("{:?}", f))
+            ));
+        }
+
+        ui.separator();
+        ui.text("Breakpoints");
+        let mut remove = None;
+        if let Some(_table) =
+            ui.begin_table_with_flags("breakpoints", 4, TableFlags::BORDERS | TableFlags::ROW_BG)
+        {
+            ui.table_setup_column("Enable");
+            ui.table_setup_column("Condition");
+            ui.table_setup_column("Hits");
+            ui.table_setup_column("");
+            ui.table_headers_row();
+            for (i, bp) in autd_server.breakpoints_mut().iter_mut().enumerate() {
+                ui.table_next_row();
+                ui.table_next_column();
+                ui.checkbox(&format!("##bp enable {}", i), &mut bp.enabled);
+                ui.table_next_column();
+                ui.text(bp.condition.description());
+                ui.table_next_column();
+                ui.text(format!("{}", bp.hits));
+                ui.table_next_column();
+                if ui.small_button(&format!("remove##bp {}", i)) {
+                    remove = Some(i);
+                }
+            }
+        }
+        if let Some(i) = remove {
+            autd_server.breakpoints_mut().remove(i);
+        }
+
+        ui.combo_simple_string(
+            "condition",
+            &mut self.bp_condition,
+            &["message type", "cpu flag", "device count change"],
+        );
+        let condition = match self.bp_condition {
+            0 => {
+                let kinds: Vec<&str> = EventKind::ALL.iter().map(|k| k.name()).collect();
+                ui.combo_simple_string("message##bp", &mut self.bp_kind, &kinds);
+                Some(BreakCondition::Kind(EventKind::ALL[self.bp_kind]))
+            }
+            1 => {
+                Slider::new("bit", 0, 7).build(ui, &mut self.bp_flag_bit);
+                // an undefined bit would be empty flags, which every packet contains
+                match CPUControlFlags::from_bits(1 << self.bp_flag_bit) {
+                    Some(flag) => {
+                        ui.text(format!("{:?}", flag));
+                        Some(BreakCondition::CpuFlag(flag))
+                    }
+                    None => {
+                        ui.text_disabled("undefined flag bit");
+                        None
+                    }
+                }
+            }
+            _ => Some(BreakCondition::DeviceCountChange),
+        };
+        let condition = match condition {
+            Some(condition) => condition,
+            None => return,
+        };
+        if ui.small_button("add breakpoint")
+            && !autd_server
+                .breakpoints()
+                .iter()
+                .any(|bp| bp.condition == condition)
+        {
+            autd_server
+                .breakpoints_mut()
+                .push(Breakpoint::new(condition));
+        }
+    }
+
+    fn traffic_tab(&mut self, ui: &Ui, autd_server: &mut AUTDServer) {
+        let traffic = autd_server.traffic();
+        let summary = traffic.summary();
+        let width = ui.content_region_avail()[0];
+
+        ui.text(format!("Elapsed: {:.1} [s]", summary.elapsed));
+        ui.text(format!(
+            "Total: {} packets, {} bytes ({} invalid)",
+            summary.total_packets, summary.total_bytes, summary.invalid_packets
+        ));
+
+        if let Some(_table) =
+            ui.begin_table_with_flags("traffic kinds", 3, TableFlags::BORDERS | TableFlags::ROW_BG)
+        {
+            ui.table_setup_column("Kind");
+            ui.table_setup_column("Count");
+            ui.table_setup_column("Rate [/s]");
+            ui.table_headers_row();
+            for k in &summary.kinds {
+                ui.table_next_row();
+                ui.table_next_column();
+                ui.text(k.kind);
+                ui.table_next_column();
+                ui.text(format!("{}", k.count));
+                ui.table_next_column();
+                ui.text(format!("{:.1}", k.rate));
+            }
+        }
+
+        ui.separator();
+        ui.text(format!("Packets: {:.1} [/s]", summary.packets_per_sec));
+        PlotLines::new(ui, "##packets per sec", &traffic.packets_per_sec_history())
+            .graph_size([width, 60.0])
+            .scale_min(0.0)
+            .build();
+        ui.text(format!(
+            "Throughput: {:.1} [bytes/s]",
+            summary.bytes_per_sec
+        ));
+        PlotLines::new(ui, "##bytes per sec", &traffic.bytes_per_sec_history())
+            .graph_size([width, 60.0])
+            .scale_min(0.0)
+            .build();
+        ui.text(format!(
+            "Backlog: {} (max {})",
+            traffic.backlog(),
+            summary.max_backlog
+        ));
+        PlotLines::new(ui, "##backlog", &traffic.backlog_history())
+            .graph_size([width, 60.0])
+            .scale_min(0.0)
+            .build();
+
+        ui.separator();
+        match &summary.drive_update_interval {
+            Some(i) => ui.text(format!(
+                "Drive update interval: {:.2} [ms] (mean {:.2}, min {:.2}, max {:.2})",
+                i.last_ms, i.mean_ms, i.min_ms, i.max_ms
+            )),
+            None => ui.text("Drive update interval: -"),
+        }
+        PlotLines::new(ui, "##drive interval", &traffic.drive_intervals())
+            .graph_size([width, 60.0])
+            .scale_min(0.0)
+            .build();
+
+        ui.text("Datagram size [bytes]");
+        PlotHistogram::new(ui, "##size histogram", &traffic.size_histogram())
+            .graph_size([width, 80.0])
+            .scale_min(0.0)
+            .build();
+        for bin in summary.size_histogram.iter().filter(|b| b.count > 0) {
+            ui.text(format!("{} - {}: {}", bin.min, bin.max, bin.count));
+        }
+
+        ui.separator();
+        if ui.small_button("copy json") {
+            match traffic.to_json() {
+                Ok(json) => ui.set_clipboard_text(json),
+                Err(e) => self.log(Record::error(format!("failed to serialize traffic: {}", e))),
+            }
+        }
+        InputText::new(ui, "path to json", &mut self.setting.traffic_json_path).build();
+        if ui.small_button("save json") {
+            let res = traffic
+                .to_json()
+                .map_err(anyhow::Error::from)
+                .and_then(|json| Ok(std::fs::write(&self.setting.traffic_json_path, json)?));
+            match res {
+                Ok(()) => self.log(Record::info(format!(
+                    "save traffic: {}",
+                    self.setting.traffic_json_path
+                ))),
+                Err(e) => self.log(Record::error(format!("failed to save traffic: {}", e))),
+            }
+        }
+        if ui.small_button("reset##traffic") {
+            autd_server.traffic_mut().reset();
+        }
+    }
+
+    fn check_consistency(&mut self, emulator: &Emulator) {
+        let warnings = consistency::check(&CheckContext::new(
+            emulator,
+            &self.fpga_states,
+            &self.setting.viewer_setting,
+        ));
+        let new_warnings: Vec<_> = warnings
+            .iter()
+            .filter(|w| !self.warnings.contains(w))
+            .map(|w| Record::warn(format!("[{}] {}", w.rule, w.message)).device(w.device))
+            .collect();
+        for record in new_warnings {
+            self.log(record);
+        }
+        self.warnings = warnings;
+    }
+
+    fn fpga_state_table(&self, ui: &Ui) {
+        let outliers = find_outliers(&self.fpga_states);
+        if outliers.iter().flatten().any(|&v| v) {
+            ui.text_colored([1.0, 0.4, 0.4, 1.0], "Some devices differ from the rest");
+        }
+        if let Some(_table) = ui.begin_table_with_flags(
+            "fpga state",
+            FpgaState::LABELS.len() + 1,
+            TableFlags::BORDERS | TableFlags::ROW_BG | TableFlags::SIZING_FIXED_FIT,
+        ) {
+            ui.table_setup_column("Device");
+            for label in FpgaState::LABELS {
+                ui.table_setup_column(label);
+            }
+            ui.table_headers_row();
+            for (i, (state, outlier)) in self.fpga_states.iter().zip(outliers.iter()).enumerate() {
+                ui.table_next_row();
+                ui.table_next_column();
+                ui.text(format!("{}", i));
+                for (value, &differ) in state.values().iter().zip(outlier.iter()) {
+                    ui.table_next_column();
+                    if differ {
+                        ui.table_set_bg_color(TableBgTarget::CELL_BG, [0.6, 0.1, 0.1, 1.0]);
+                    }
+                    ui.text(value);
+                }
+            }
+        }
+    }
+
+    fn update_modulation(&mut self, modulation: (Vec<u8>, u32)) {
+        self.mod_analysis = ModulationAnalysis::new(&modulation.0, modulation.1);
+        self.mod_spectrum_max = (self.mod_analysis.sampling_freq / 2.0) as f32;
+        self.modulation = modulation;
+    }
+
     fn mod_values<F>(&self, f: F) -> Vec<f32>
     where
         F: Fn(&u8) -> f32,
@@ -1047,45 +3344,256 @@ impl App {
         self.modulation.0.iter().map(f).collect()
     }
 
-    // TODO: This log system is not so efficient
-    fn log(&mut self, msg: &str) {
-        if self.setting.log_enable {
-            let date = chrono::Local::now();
-            self.log_buf
-                .push_back(format!("{}: {}", date.format("%Y-%m-%d %H:%M:%S.%3f"), msg));
-            while self.log_buf.len() > self.setting.log_max as usize {
-                self.log_buf.pop_front();
-            }
+    fn log(&mut self, record: Record) {
+        if !self.setting.log_enable {
+            return;
+        }
+        if let Err(e) = self.logger.push(record) {
+            self.logger.close_file();
+            self.setting.log_file_enable = false;
+            self.log(Record::error(format!(
+                "failed to write log file, file output is disabled: {}",
+                e
+            )));
         }
     }
 
-    fn log_clear(&mut self) {
-        self.log_buf.clear();
+    fn open_log_file(&mut self) {
+        if !self.setting.log_file_enable {
+            self.logger.close_file();
+            return;
+        }
+        if let Err(e) = self.logger.open_file(
+            &self.setting.log_file_path,
+            self.setting.log_file_max_size as u64 * 1024,
+            self.setting.log_file_count as _,
+        ) {
+            self.setting.log_file_enable = false;
+            self.log(Record::error(format!("failed to open log file: {}", e)));
+        }
     }
 
-    fn get_log_txt(&self) -> String {
-        let mut log = String::new();
-        for line in &self.log_buf {
-            log.push_str(line);
-            log.push('\n');
+    fn log_tab(&mut self, ui: &Ui) {
+        if ui.radio_button_bool("enable", self.setting.log_enable) {
+            self.setting.log_enable = !self.setting.log_enable;
+        }
+        ui.same_line();
+        if ui.small_button("clear") {
+            self.logger.clear();
+        }
+        if Slider::new("Max", 0, 10000).build(ui, &mut self.setting.log_max) {
+            self.logger.set_max(self.setting.log_max as _);
+        }
+
+        if ui.checkbox("write to file", &mut self.setting.log_file_enable) {
+            self.open_log_file();
         }
-        log
+        InputText::new(ui, "path to log", &mut self.setting.log_file_path).build();
+        let mut reopen = ui.is_item_deactivated_after_edit();
+        reopen |= Drag::new("max file size [KiB]")
+            .range(1, u32::MAX)
+            .build(ui, &mut self.setting.log_file_max_size);
+        reopen |= Drag::new("rotated files")
+            .range(0, 100)
+            .build(ui, &mut self.setting.log_file_count);
+        // the open file keeps the old path and limits until it is reopened
+        if reopen && self.setting.log_file_enable {
+            self.open_log_file();
+        }
+
+        ui.separator();
+        let levels: Vec<&str> = Level::ALL.iter().map(|l| l.name()).collect();
+        ui.combo_simple_string("level", &mut self.log_level_filter, &levels);
+        let kinds: Vec<&str> = ["all", "app"]
+            .into_iter()
+            .chain(EventKind::ALL.iter().map(|k| k.name()))
+            .collect();
+        ui.combo_simple_string("kind##log", &mut self.log_kind_filter, &kinds);
+
+        let min_level = Level::ALL[self.log_level_filter];
+        let kind_filter = self.log_kind_filter;
+        let records: Vec<&Record> = self
+            .logger
+            .records()
+            .iter()
+            .filter(|r| r.level >= min_level)
+            .filter(|r| match kind_filter {
+                0 => true,
+                1 => r.kind.is_none(),
+                i => r.kind == Some(EventKind::ALL[i - 2]),
+            })
+            .collect();
+
+        ChildWindow::new("log records")
+            .border(true)
+            .horizontal_scrollbar(true)
+            .build(ui, || {
+                let mut clipper = ListClipper::new(records.len() as i32)
+                    .items_height(ui.text_line_height_with_spacing())
+                    .begin(ui);
+                while clipper.step() {
+                    for i in clipper.display_start()..clipper.display_end() {
+                        let r = records[i as usize];
+                        ui.text_colored(r.level.color(), r.format());
+                    }
+                }
+                if ui.scroll_y() >= ui.scroll_max_y() {
+                    ui.set_scroll_here_y_with_ratio(1.0);
+                }
+            });
     }
 
     fn update_drive(&mut self, idx: usize) {
-        self.sources
+        let sound_speed = self.setting.viewer_setting.sound_speed;
+        match self.history_cursor.and_then(|id| self.history.find(id)) {
+            Some(s) => Self::set_drives(
+                &mut self.sources,
+                &s.drives,
+                &s.cycles,
+                s.static_mod,
+                idx,
+                sound_speed,
+            ),
+            None => Self::set_drives(
+                &mut self.sources,
+                &self.drives,
+                &self.cycles,
+                self.static_mod,
+                idx,
+                sound_speed,
+            ),
+        }
+    }
+
+    fn set_drives(
+        sources: &mut SoundSources,
+        drives: &Drives,
+        cycles: &[u16],
+        static_mod: f32,
+        idx: usize,
+        sound_speed: f32,
+    ) {
+        let idx = if drives.iter().all(|d| idx < d.len()) {
+            idx
+        } else {
+            0
+        };
+        sources
             .drives_mut()
-            .zip(self.drives.iter().flat_map(|d| d[idx].0))
-            .zip(self.drives.iter().flat_map(|d| d[idx].1))
-            .zip(self.cycles.iter())
+            .zip(drives.iter().flat_map(|d| d[idx].0))
+            .zip(drives.iter().flat_map(|d| d[idx].1))
+            .zip(cycles.iter())
             .for_each(|(((drive, duty), phase), cycle)| {
-                drive.amp = (PI * self.static_mod * duty.duty as f32 / *cycle as f32).sin();
+                drive.amp = (PI * static_mod * duty.duty as f32 / *cycle as f32).sin();
                 drive.phase = 2.0 * PI * (*cycle - phase.phase) as f32 / *cycle as f32;
-                drive.set_wave_number(
-                    FPGA_CLK_FREQ as f32 / *cycle as f32,
-                    self.setting.viewer_setting.sound_speed,
-                );
+                drive.set_wave_number(FPGA_CLK_FREQ as f32 / *cycle as f32, sound_speed);
+            });
+    }
+
+    fn history_tab(&mut self, ui: &Ui) -> UpdateFlag {
+        let mut update_flag = UpdateFlag::empty();
+
+        let mut changed = Slider::new("Max", 1, 1000).build(ui, &mut self.setting.history_max);
+        changed |= Drag::new("Max size [MiB]")
+            .range(1, 4096)
+            .build(ui, &mut self.setting.history_max_size);
+        if changed {
+            self.history.set_max(
+                self.setting.history_max as _,
+                self.setting.history_max_size as usize * 1024 * 1024,
+            );
+        }
+        ui.text(format!(
+            "Size: {:.2} [MiB]",
+            self.history.bytes() as f64 / (1024.0 * 1024.0)
+        ));
+        ui.same_line();
+        if ui.small_button("clear##history") {
+            self.history.clear();
+        }
+
+        let n = self.history.snapshots().len();
+        let mut cursor = self.history_cursor.and_then(|id| self.history.position(id));
+        if self.history_cursor.is_some() && cursor.is_none() {
+            // the state being viewed has been dropped from the history
+            cursor = if n > 0 { Some(0) } else { None };
+        }
+
+        if ui.radio_button_bool("live", cursor.is_none()) {
+            cursor = None;
+        }
+        if n > 0 {
+            let mut pos = cursor.unwrap_or(n - 1) as i32;
+            if Slider::new("timeline", 0, n as i32 - 1).build(ui, &mut pos) {
+                cursor = Some(pos as usize);
+            }
+            ui.same_line();
+            if ui.arrow_button("##history prev", Direction::Left) {
+                cursor = Some(cursor.unwrap_or(n - 1).saturating_sub(1));
+            }
+            ui.same_line();
+            if ui.arrow_button("##history next", Direction::Right) {
+                cursor = cursor.map(|c| (c + 1).min(n - 1));
+            }
+        }
+
+        ChildWindow::new("history list")
+            .size([0.0, 200.0])
+            .border(true)
+            .build(ui, || {
+                let snapshots = self.history.snapshots();
+                let mut clipper = ListClipper::new(n as i32)
+                    .items_height(ui.text_line_height_with_spacing())
+                    .begin(ui);
+                while clipper.step() {
+                    for i in clipper.display_start()..clipper.display_end() {
+                        let s = &snapshots[i as usize];
+                        let label = format!(
+                            "#{} {} msg_id {:3} {}",
+                            s.id,
+                            s.time.format("%H:%M:%S%.3f"),
+                            s.msg_id,
+                            s.kind.name()
+                        );
+                        if Selectable::new(&label)
+                            .selected(cursor == Some(i as usize))
+                            .build(ui)
+                        {
+                            cursor = Some(i as usize);
+                        }
+                    }
+                }
             });
+
+        let new_cursor = cursor.map(|c| self.history.snapshots()[c].id);
+        if new_cursor != self.history_cursor {
+            self.history_cursor = new_cursor;
+            update_flag |= UpdateFlag::UPDATE_SOURCE_DRIVE;
+            self.update_drive(self.stm_idx as usize);
+        }
+
+        if let Some(s) = self.history_cursor.and_then(|id| self.history.find(id)) {
+            ui.separator();
+            ui.text(format!(
+                "#{} {}",
+                s.id,
+                s.time.format("%Y-%m-%d %H:%M:%S%.6f")
+            ));
+            ui.text(format!("msg_id: {} ({})", s.msg_id, s.kind.name()));
+            ui.text(format!("Devices: {}", s.drives.len()));
+            if s.is_stm_mode {
+                ui.text(format!("STM size: {}", s.stm_size()));
+            }
+            if let Some((modulation, div)) = s.modulations.first() {
+                ui.text(format!(
+                    "Modulation: {} samples, division {}",
+                    modulation.len(),
+                    div
+                ));
+            }
+        }
+
+        update_flag
     }
 }
 
@@ -1120,7 +3628,10 @@ fn init_imgui(renderer: &Renderer) -> (Context, WinitPlatform, imgui_vulkano_ren
 }
 
 pub fn main() -> Result<()> {
-    let setting = Setting::load("setting.json");
+    let (setting, setting_err) = match Setting::load("setting.json") {
+        Ok(setting) => (setting, None),
+        Err(e) => (Setting::new(), Some(e)),
+    };
 
     let mut event_loop = EventLoop::new();
     let mut renderer = Renderer::new(
@@ -1132,11 +3643,28 @@ pub fn main() -> Result<()> {
     );
 
     let mut app = App::new(setting, &renderer);
+    app.open_log_file();
+    if let Some(e) = setting_err {
+        app.log(Record::error(format!(
+            "failed to load setting.json, the default settings are used: {}",
+            e
+        )));
+    }
+    if app.setting.viewer_setting.color_map == ColorMapKind::Custom {
+        app.load_custom_color_map();
+    }
     app.reset(&mut renderer);
 
     let (mut imgui, mut platform, mut imgui_renderer) = init_imgui(&renderer);
 
-    let mut autd_server = AUTDServer::new(&format!("127.0.0.1:{}", app.setting.port))?;
+    let mut autd_server = AUTDServer::new(
+        &format!("127.0.0.1:{}", app.setting.port),
+        app.setting.packet_max as _,
+    )?;
+    if !app.setting.geometry_file.is_empty() {
+        let update_flag = app.load_geometry_file(&mut autd_server);
+        app.update_view(&mut renderer, update_flag);
+    }
 
     let mut is_running = true;
     let mut last_frame = Instant::now();
@@ -1199,12 +3727,31 @@ pub fn main() -> Result<()> {
                         / app.setting.viewer_setting.slice_pixel_size;
                     let height = app.setting.viewer_setting.slice_height
                         / app.setting.viewer_setting.slice_pixel_size;
-                    let pixels: Vec<_> = (&result[0..(width as usize * height as usize)])
+                    let mut pixels: Vec<_> = (&result[0..(width as usize * height as usize)])
                         .chunks_exact(width as _)
                         .rev()
                         .flatten()
                         .flat_map(|&c| vecmath_util::vec4_map(c, |v| (v * 255.0) as u8))
                         .collect();
+                    if app.setting.show_contours
+                        && app.setting.contour_in_image
+                        && app.contour_size == [width, height]
+                    {
+                        contour::draw_on_image(&mut pixels, width, height, &app.contours);
+                    }
+                    let (pixels, width) = if app.setting.colorbar_in_image {
+                        let (ticks, unit) = app.colorbar_ticks();
+                        colorbar::append_to_image(
+                            &pixels,
+                            width,
+                            height,
+                            &app.colorbar_colors,
+                            &ticks,
+                            unit,
+                        )
+                    } else {
+                        (pixels, width)
+                    };
 
                     if app.save_image {
                         let output = File::create(&app.setting.save_file_path).unwrap();
@@ -0,0 +1,237 @@
+/*
+ * File: modulation.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    f32::consts::PI,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use autd3_core::FPGA_CLK_FREQ;
+
+pub struct ModulationAnalysis {
+    pub envelope: Vec<f32>,
+    pub sampling_freq: f64,
+    pub rms: f32,
+    pub peak: f32,
+    pub mean: f32,
+    pub spectrum: Vec<f32>,
+    pub freq_resolution: f64,
+}
+
+impl ModulationAnalysis {
+    pub fn new(modulation: &[u8], freq_div: u32) -> Self {
+        let envelope: Vec<f32> = modulation.iter().map(|&v| Self::to_amp(v)).collect();
+        let sampling_freq = if freq_div == 0 {
+            0.0
+        } else {
+            FPGA_CLK_FREQ as f64 / freq_div as f64
+        };
+
+        let n = envelope.len();
+        let (rms, peak, mean) = if n == 0 {
+            (0.0, 0.0, 0.0)
+        } else {
+            let rms = (envelope.iter().map(|v| v * v).sum::<f32>() / n as f32).sqrt();
+            let peak = envelope.iter().fold(0.0f32, |acc, &v| acc.max(v.abs()));
+            let mean = envelope.iter().sum::<f32>() / n as f32;
+            (rms, peak, mean)
+        };
+
+        let (spectrum, freq_resolution) = if n == 0 {
+            (vec![], 0.0)
+        } else {
+            let n_fft = n.next_power_of_two();
+            let mut re = envelope.clone();
+            re.resize(n_fft, 0.0);
+            let mut im = vec![0.0; n_fft];
+            fft(&mut re, &mut im);
+            let spectrum = re
+                .iter()
+                .zip(im.iter())
+                .take(n_fft / 2 + 1)
+                .enumerate()
+                .map(|(k, (r, i))| {
+                    let scale = if k == 0 { 1.0 } else { 2.0 };
+                    scale * (r * r + i * i).sqrt() / n as f32
+                })
+                .collect();
+            (spectrum, sampling_freq / n_fft as f64)
+        };
+
+        Self {
+            envelope,
+            sampling_freq,
+            rms,
+            peak,
+            mean,
+            spectrum,
+            freq_resolution,
+        }
+    }
+
+    pub fn to_amp(v: u8) -> f32 {
+        ((v as f32) / 512.0 * PI).sin()
+    }
+
+    pub fn peak_frequency(&self) -> Option<(f64, f32)> {
+        self.spectrum
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(k, &m)| (k as f64 * self.freq_resolution, m))
+    }
+
+    pub fn save_wav<P: AsRef<Path>>(&self, path: P, duration: f32) -> Result<()> {
+        if self.envelope.is_empty() || self.sampling_freq < 1.0 {
+            return Err(anyhow!("Modulation is empty"));
+        }
+        let sample_rate = self.sampling_freq.round() as u32;
+        let len = ((duration as f64 * self.sampling_freq) as usize).max(self.envelope.len());
+        if len > MAX_WAV_SAMPLES {
+            return Err(anyhow!("Duration is too long for a WAV file"));
+        }
+        let samples: Vec<f32> = self.envelope.iter().cycle().take(len).copied().collect();
+        write_wav(path, &samples, sample_rate)
+    }
+}
+
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j ^= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        let ang = -2.0 * std::f64::consts::PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..half {
+                let (s, c) = (ang * k as f64).sin_cos();
+                let (s, c) = (s as f32, c as f32);
+                let (ur, ui) = (re[start + k], im[start + k]);
+                let (xr, xi) = (re[start + k + half], im[start + k + half]);
+                let vr = xr * c - xi * s;
+                let vi = xr * s + xi * c;
+                re[start + k] = ur + vr;
+                im[start + k] = ui + vi;
+                re[start + k + half] = ur - vr;
+                im[start + k + half] = ui - vi;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// the RIFF chunk size, 36 + data size, must fit in u32
+const MAX_WAV_SAMPLES: usize = (u32::MAX as usize - 36) / 2;
+
+// 16-bit PCM, mono; samples in [0, 1] are mapped to the full range
+fn write_wav<P: AsRef<Path>>(path: P, samples: &[f32], sample_rate: u32) -> Result<()> {
+    let data_len = u32::try_from(samples.len() * 2)
+        .ok()
+        .filter(|l| l.checked_add(36).is_some())
+        .ok_or_else(|| anyhow!("Duration is too long for a WAV file"))?;
+    let byte_rate = sample_rate
+        .checked_mul(2)
+        .ok_or_else(|| anyhow!("Sampling frequency is too high for a WAV file"))?;
+    let mut w = BufWriter::new(File::create(path)?);
+    w.write_all(b"RIFF")?;
+    w.write_all(&(36 + data_len).to_le_bytes())?;
+    w.write_all(b"WAVE")?;
+    w.write_all(b"fmt ")?;
+    w.write_all(&16u32.to_le_bytes())?;
+    w.write_all(&1u16.to_le_bytes())?;
+    w.write_all(&1u16.to_le_bytes())?;
+    w.write_all(&sample_rate.to_le_bytes())?;
+    w.write_all(&byte_rate.to_le_bytes())?;
+    w.write_all(&2u16.to_le_bytes())?;
+    w.write_all(&16u16.to_le_bytes())?;
+    w.write_all(b"data")?;
+    w.write_all(&data_len.to_le_bytes())?;
+    for &v in samples {
+        let s = ((2.0 * v - 1.0).clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        w.write_all(&s.to_le_bytes())?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fft_of_pure_tone() {
+        let n = 64;
+        let k0 = 5;
+        let mut re: Vec<f32> = (0..n)
+            .map(|i| (2.0 * PI * k0 as f32 * i as f32 / n as f32).cos())
+            .collect();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        for k in 0..n {
+            let mag = re[k].hypot(im[k]);
+            let expected = if k == k0 || k == n - k0 {
+                n as f32 / 2.0
+            } else {
+                0.0
+            };
+            assert!((mag - expected).abs() < 1e-3, "bin {}: {}", k, mag);
+        }
+    }
+
+    #[test]
+    fn fft_of_impulse() {
+        let n = 16;
+        let mut re = vec![0.0; n];
+        re[0] = 1.0;
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        assert!(re.iter().all(|&v| (v - 1.0).abs() < 1e-6));
+        assert!(im.iter().all(|&v| v.abs() < 1e-6));
+    }
+
+    #[test]
+    fn spectrum_peak_of_tone() {
+        // 8 cycles over 256 samples around the middle of the duty range
+        let modulation: Vec<u8> = (0..256)
+            .map(|i| (128.0 + 100.0 * (2.0 * PI * 8.0 * i as f32 / 256.0).sin()) as u8)
+            .collect();
+        let analysis = ModulationAnalysis::new(&modulation, 40960);
+        let (freq, _) = analysis.peak_frequency().unwrap();
+        assert!((freq - 8.0 * analysis.freq_resolution).abs() < 1e-9);
+    }
+
+    #[test]
+    fn wav_too_long() {
+        let analysis = ModulationAnalysis::new(&[255; 4], 40960);
+        let path = std::env::temp_dir().join("autd3_emulator_wav_too_long.wav");
+        assert!(analysis.save_wav(&path, 1e7).is_err());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,228 @@
+/*
+ * File: packet.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::collections::VecDeque;
+
+use autd3_core::{
+    Body, CPUControlFlags, FPGAControlFlags, GlobalHeader, MSG_CLEAR, MSG_EMU_GEOMETRY_SET,
+    MSG_RD_CPU_VERSION, MSG_RD_FPGA_FUNCTION, MSG_RD_FPGA_VERSION, NUM_TRANS_IN_UNIT,
+};
+use chrono::{DateTime, Local};
+
+// emulator-only messages to reset the devices from a test client, outside the msg_id range of
+// the normal messages; the byte after the header of a power cycle is 1 to keep the geometry
+// (kept if missing), and the two bytes after the header of a reboot are the device index
+pub const MSG_EMU_POWER_CYCLE: u8 = 0xF8;
+pub const MSG_EMU_REBOOT: u8 = 0xF9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Clear,
+    RequestFpgaVersion,
+    RequestFpgaFunctions,
+    RequestCpuVersion,
+    Normal,
+    Geometries,
+    PowerCycle,
+    Reboot,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 8] = [
+        EventKind::Clear,
+        EventKind::RequestFpgaVersion,
+        EventKind::RequestFpgaFunctions,
+        EventKind::RequestCpuVersion,
+        EventKind::Normal,
+        EventKind::Geometries,
+        EventKind::PowerCycle,
+        EventKind::Reboot,
+    ];
+
+    pub fn from_msg_id(msg_id: u8) -> Self {
+        match msg_id {
+            MSG_EMU_GEOMETRY_SET => EventKind::Geometries,
+            MSG_EMU_POWER_CYCLE => EventKind::PowerCycle,
+            MSG_EMU_REBOOT => EventKind::Reboot,
+            MSG_CLEAR => EventKind::Clear,
+            MSG_RD_CPU_VERSION => EventKind::RequestCpuVersion,
+            MSG_RD_FPGA_VERSION => EventKind::RequestFpgaVersion,
+            MSG_RD_FPGA_FUNCTION => EventKind::RequestFpgaFunctions,
+            _ => EventKind::Normal,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::Clear => "clear",
+            EventKind::RequestFpgaVersion => "req fpga ver",
+            EventKind::RequestFpgaFunctions => "req fpga functions",
+            EventKind::RequestCpuVersion => "req cpu ver",
+            EventKind::Normal => "normal",
+            EventKind::Geometries => "geometry",
+            EventKind::PowerCycle => "power cycle",
+            EventKind::Reboot => "reboot",
+        }
+    }
+}
+
+pub enum BodyFormat {
+    Legacy,
+    Duty,
+    Phase,
+    Raw,
+}
+
+impl BodyFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BodyFormat::Legacy => "legacy (duty/phase)",
+            BodyFormat::Duty => "duty",
+            BodyFormat::Phase => "phase",
+            BodyFormat::Raw => "raw",
+        }
+    }
+}
+
+pub struct Packet {
+    pub seq: u64,
+    pub time: DateTime<Local>,
+    pub raw: Vec<u8>,
+}
+
+impl Packet {
+    pub fn new(raw: Vec<u8>) -> Self {
+        Self {
+            seq: 0,
+            time: Local::now(),
+            raw,
+        }
+    }
+
+    pub fn header(&self) -> Option<&GlobalHeader> {
+        if self.raw.len() < std::mem::size_of::<GlobalHeader>() {
+            return None;
+        }
+        unsafe { (self.raw.as_ptr() as *const GlobalHeader).as_ref() }
+    }
+
+    pub fn kind(&self) -> Option<EventKind> {
+        self.header().map(|h| EventKind::from_msg_id(h.msg_id))
+    }
+
+    pub fn fpga_flag(&self) -> Option<FPGAControlFlags> {
+        self.header().map(|h| h.fpga_flag)
+    }
+
+    pub fn cpu_flag(&self) -> Option<CPUControlFlags> {
+        self.header().map(|h| h.cpu_flag)
+    }
+
+    pub fn num_bodies(&self) -> usize {
+        self.raw
+            .len()
+            .saturating_sub(std::mem::size_of::<GlobalHeader>())
+            / std::mem::size_of::<Body>()
+    }
+
+    pub fn body(&self, idx: usize) -> Vec<u16> {
+        let offset = std::mem::size_of::<GlobalHeader>() + idx * std::mem::size_of::<Body>();
+        self.raw[offset..offset + std::mem::size_of::<Body>()]
+            .chunks_exact(std::mem::size_of::<u16>())
+            .take(NUM_TRANS_IN_UNIT)
+            .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+            .collect()
+    }
+
+    pub fn body_format(&self) -> BodyFormat {
+        match (self.kind(), self.fpga_flag(), self.cpu_flag()) {
+            (Some(EventKind::Normal), Some(fpga_flag), Some(cpu_flag))
+                if cpu_flag.contains(CPUControlFlags::WRITE_BODY) =>
+            {
+                if fpga_flag.contains(FPGAControlFlags::STM_MODE)
+                    && !fpga_flag.contains(FPGAControlFlags::STM_GAIN_MODE)
+                {
+                    BodyFormat::Raw
+                } else if fpga_flag.contains(FPGAControlFlags::LEGACY_MODE) {
+                    BodyFormat::Legacy
+                } else if cpu_flag.contains(CPUControlFlags::IS_DUTY) {
+                    BodyFormat::Duty
+                } else {
+                    BodyFormat::Phase
+                }
+            }
+            _ => BodyFormat::Raw,
+        }
+    }
+
+    pub fn hex_dump(&self) -> Vec<String> {
+        self.raw
+            .chunks(16)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+                let ascii: String = chunk
+                    .iter()
+                    .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+                    .collect();
+                format!("{:06x}: {:<47} |{}|", i * 16, hex.join(" "), ascii)
+            })
+            .collect()
+    }
+}
+
+pub struct PacketLog {
+    packets: VecDeque<Packet>,
+    max: usize,
+    next_seq: u64,
+}
+
+impl PacketLog {
+    pub fn new(max: usize) -> Self {
+        Self {
+            packets: VecDeque::new(),
+            max,
+            next_seq: 0,
+        }
+    }
+
+    pub fn push(&mut self, mut packet: Packet) {
+        packet.seq = self.next_seq;
+        self.next_seq += 1;
+        self.packets.push_back(packet);
+        self.truncate();
+    }
+
+    pub fn set_max(&mut self, max: usize) {
+        self.max = max;
+        self.truncate();
+    }
+
+    pub fn clear(&mut self) {
+        self.packets.clear();
+    }
+
+    pub fn packets(&self) -> &VecDeque<Packet> {
+        &self.packets
+    }
+
+    pub fn find(&self, seq: u64) -> Option<&Packet> {
+        self.packets.iter().find(|p| p.seq == seq)
+    }
+
+    fn truncate(&mut self) {
+        while self.packets.len() > self.max {
+            self.packets.pop_front();
+        }
+    }
+}
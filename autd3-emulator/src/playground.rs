@@ -0,0 +1,198 @@
+/*
+ * File: playground.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::f32::consts::PI;
+
+use acoustic_field_viewer::{sound_sources::SoundSources, Vector3};
+use autd3_core::TRANS_SPACING_MM;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    Focus,
+    MultiFocus,
+    Bessel,
+    PlaneWave,
+    Null,
+    Uniform,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 6] = [
+        Pattern::Focus,
+        Pattern::MultiFocus,
+        Pattern::Bessel,
+        Pattern::PlaneWave,
+        Pattern::Null,
+        Pattern::Uniform,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Focus => "focus",
+            Pattern::MultiFocus => "multi focus",
+            Pattern::Bessel => "bessel beam",
+            Pattern::PlaneWave => "plane wave",
+            Pattern::Null => "null",
+            Pattern::Uniform => "uniform",
+        }
+    }
+}
+
+pub struct Playground {
+    pub enabled: bool,
+    pub pattern: Pattern,
+    pub amp: f32,
+    pub focus: Vector3,
+    pub foci: Vec<(Vector3, f32)>,
+    pub bessel_apex: Vector3,
+    pub bessel_dir: Vector3,
+    pub bessel_theta: f32,
+    pub plane_dir: Vector3,
+    pub uniform_phase: f32,
+}
+
+impl Playground {
+    pub fn new() -> Self {
+        // above the center of a single device
+        let center = [
+            TRANS_SPACING_MM as f32 * 8.5,
+            TRANS_SPACING_MM as f32 * 6.5,
+            150.0,
+        ];
+        Self {
+            enabled: false,
+            pattern: Pattern::Focus,
+            amp: 1.0,
+            focus: center,
+            foci: vec![
+                ([center[0] - 20.0, center[1], center[2]], 1.0),
+                ([center[0] + 20.0, center[1], center[2]], 1.0),
+            ],
+            bessel_apex: [center[0], center[1], 0.0],
+            bessel_dir: [0.0, 0.0, 1.0],
+            bessel_theta: 18.0 / 180.0 * PI,
+            plane_dir: [0.0, 0.0, 1.0],
+            uniform_phase: 0.0,
+        }
+    }
+
+    pub fn apply(&self, sources: &mut SoundSources) {
+        match self.pattern {
+            Pattern::Focus => self.apply_focus(sources),
+            Pattern::MultiFocus => self.apply_multi_focus(sources),
+            Pattern::Bessel => self.apply_bessel(sources),
+            Pattern::PlaneWave => self.apply_plane_wave(sources),
+            Pattern::Null => sources.drives_mut().for_each(|d| d.amp = 0.0),
+            Pattern::Uniform => sources.drives_mut().for_each(|d| {
+                d.amp = self.amp;
+                d.phase = self.uniform_phase;
+            }),
+        }
+    }
+
+    // points that can be dragged with the focus gizmo
+    pub fn focus_points(&self) -> Vec<Vector3> {
+        if !self.enabled {
+            return vec![];
+        }
+        match self.pattern {
+            Pattern::Focus => vec![self.focus],
+            Pattern::MultiFocus => self.foci.iter().map(|f| f.0).collect(),
+            _ => vec![],
+        }
+    }
+
+    pub fn set_focus_point(&mut self, idx: usize, pos: Vector3) {
+        match self.pattern {
+            Pattern::Focus => self.focus = pos,
+            Pattern::MultiFocus => {
+                if let Some(f) = self.foci.get_mut(idx) {
+                    f.0 = pos;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_focus(&self, sources: &mut SoundSources) {
+        for (pos, drive) in sources.positions_drives_mut() {
+            let r = vecmath_util::dist(vecmath_util::to_vec3(pos), self.focus);
+            drive.amp = self.amp;
+            drive.phase = Self::wrap(-drive.wave_num * r);
+        }
+    }
+
+    // naive back propagation: each transducer emits the conjugate sum of its transfer to every focus
+    fn apply_multi_focus(&self, sources: &mut SoundSources) {
+        let q: Vec<(f32, f32)> = sources
+            .positions_drives_mut()
+            .map(|(pos, drive)| {
+                self.foci.iter().fold((0.0, 0.0), |(re, im), (f, a)| {
+                    let r = vecmath_util::dist(vecmath_util::to_vec3(pos), *f);
+                    let p = drive.wave_num * r;
+                    (re + a * p.cos(), im + a * p.sin())
+                })
+            })
+            .collect();
+        let max = q
+            .iter()
+            .map(|(re, im)| re.hypot(*im))
+            .fold(0.0f32, f32::max);
+        for ((_, drive), (re, im)) in sources.positions_drives_mut().zip(q) {
+            drive.amp = if max > 0.0 {
+                self.amp * re.hypot(im) / max
+            } else {
+                0.0
+            };
+            drive.phase = Self::wrap(-im.atan2(re));
+        }
+    }
+
+    fn apply_bessel(&self, sources: &mut SoundSources) {
+        let dir = Self::normalize(self.bessel_dir);
+        for (pos, drive) in sources.positions_drives_mut() {
+            let r = vecmath::vec3_sub(vecmath_util::to_vec3(pos), self.bessel_apex);
+            let z = vecmath::vec3_dot(r, dir);
+            let rho = vecmath::vec3_len(vecmath::vec3_sub(r, vecmath::vec3_scale(dir, z)));
+            drive.amp = self.amp;
+            drive.phase = Self::wrap(-drive.wave_num * rho * self.bessel_theta.sin());
+        }
+    }
+
+    fn apply_plane_wave(&self, sources: &mut SoundSources) {
+        let dir = Self::normalize(self.plane_dir);
+        for (pos, drive) in sources.positions_drives_mut() {
+            let d = vecmath::vec3_dot(vecmath_util::to_vec3(pos), dir);
+            drive.amp = self.amp;
+            drive.phase = Self::wrap(drive.wave_num * d);
+        }
+    }
+
+    fn normalize(v: Vector3) -> Vector3 {
+        if vecmath::vec3_len(v) > 0.0 {
+            vecmath::vec3_normalized(v)
+        } else {
+            [0.0, 0.0, 1.0]
+        }
+    }
+
+    fn wrap(phase: f32) -> f32 {
+        phase.rem_euclid(2.0 * PI)
+    }
+}
+
+impl Default for Playground {
+    fn default() -> Self {
+        Self::new()
+    }
+}
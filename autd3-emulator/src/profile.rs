@@ -0,0 +1,269 @@
+/*
+ * File: profile.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use acoustic_field_viewer::{sound_sources::SoundSources, Matrix4, Vector2, Vector3};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineSpace {
+    // x and y on the slice plane, z is ignored
+    Slice,
+    World,
+}
+
+impl LineSpace {
+    pub const ALL: [LineSpace; 2] = [LineSpace::Slice, LineSpace::World];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineSpace::Slice => "slice coordinates",
+            LineSpace::World => "3D points",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct ProfileLine {
+    pub space: LineSpace,
+    pub start: Vector3,
+    pub end: Vector3,
+    pub samples: u32,
+}
+
+impl ProfileLine {
+    pub fn new() -> Self {
+        Self {
+            space: LineSpace::Slice,
+            start: [-50.0, 0.0, 0.0],
+            end: [50.0, 0.0, 0.0],
+            samples: 200,
+        }
+    }
+
+    // end points in world coordinates; slice_model is the model matrix of the slice
+    pub fn end_points(&self, slice_model: &Matrix4) -> [Vector3; 2] {
+        [self.start, self.end].map(|p| match self.space {
+            LineSpace::Slice => {
+                let q = vecmath::col_mat4_transform(*slice_model, [p[0], p[1], 0.0, 1.0]);
+                [q[0], q[1], q[2]]
+            }
+            LineSpace::World => p,
+        })
+    }
+}
+
+impl Default for ProfileLine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Profile {
+    pub points: Vec<Vector3>,
+    pub field: Vec<Vector2>,
+    pub amplitudes: Vec<f32>,
+    step: f32,
+}
+
+impl Profile {
+    pub fn sample(sources: &SoundSources, [start, end]: [Vector3; 2], samples: u32) -> Self {
+        let n = samples.max(2) as usize;
+        let d = vecmath::vec3_sub(end, start);
+        let points: Vec<Vector3> = (0..n)
+            .map(|i| vecmath::vec3_add(start, vecmath::vec3_scale(d, i as f32 / (n - 1) as f32)))
+            .collect();
+        let field: Vec<Vector2> = points.iter().map(|&p| sources.pressure_at(p)).collect();
+        let amplitudes = field.iter().map(|v| v[0].hypot(v[1])).collect();
+        Self {
+            points,
+            field,
+            amplitudes,
+            step: vecmath::vec3_len(d) / (n - 1) as f32,
+        }
+    }
+
+    pub fn distance(&self, i: usize) -> f32 {
+        i as f32 * self.step
+    }
+
+    pub fn peak(&self) -> Option<(usize, f32)> {
+        self.amplitudes
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    // full width at half of the peak amplitude, None if the main lobe is cut by the line ends
+    pub fn fwhm(&self) -> Option<f32> {
+        let (peak, max) = self.peak()?;
+        let half = max / 2.0;
+        let a = &self.amplitudes;
+        let left = (1..=peak).rev().find(|&i| a[i - 1] < half).map(|i| {
+            let t = (a[i] - half) / (a[i] - a[i - 1]);
+            self.distance(i) - t * self.step
+        })?;
+        let right = (peak..a.len() - 1).find(|&i| a[i + 1] < half).map(|i| {
+            let t = (a[i] - half) / (a[i] - a[i + 1]);
+            self.distance(i) + t * self.step
+        })?;
+        Some(right - left)
+    }
+
+    // the main lobe extends from the peak to the first minimum on each side
+    fn main_lobe(&self) -> Option<(usize, usize)> {
+        let (peak, _) = self.peak()?;
+        let a = &self.amplitudes;
+        let mut left = peak;
+        while left > 0 && a[left - 1] <= a[left] {
+            left -= 1;
+        }
+        let mut right = peak;
+        while right + 1 < a.len() && a[right + 1] <= a[right] {
+            right += 1;
+        }
+        Some((left, right))
+    }
+
+    // highest amplitude outside the main lobe relative to the peak [dB]
+    pub fn side_lobe_level(&self) -> Option<f32> {
+        let (_, max) = self.peak()?;
+        let (left, right) = self.main_lobe()?;
+        let side = self.amplitudes[..left]
+            .iter()
+            .chain(self.amplitudes[right + 1..].iter())
+            .copied()
+            .max_by(f32::total_cmp)?;
+        if max <= 0.0 || side <= 0.0 {
+            return None;
+        }
+        Some(20.0 * (side / max).log10())
+    }
+
+    // pressure is in Pa, scaled by source_amplitude
+    pub fn save_csv<P: AsRef<Path>>(&self, path: P, source_amplitude: f32) -> Result<PathBuf> {
+        let path = path.as_ref().with_extension("csv");
+        let mut w = BufWriter::new(File::create(&path)?);
+        if let (Some(start), Some(end)) = (self.points.first(), self.points.last()) {
+            writeln!(w, "# start: {} {} {}", start[0], start[1], start[2])?;
+            writeln!(w, "# end: {} {} {}", end[0], end[1], end[2])?;
+        }
+        if let Some((i, max)) = self.peak() {
+            writeln!(
+                w,
+                "# peak: {} at {}",
+                max * source_amplitude,
+                self.distance(i)
+            )?;
+        }
+        if let Some(fwhm) = self.fwhm() {
+            writeln!(w, "# fwhm: {}", fwhm)?;
+        }
+        if let Some(level) = self.side_lobe_level() {
+            writeln!(w, "# side lobe level [dB]: {}", level)?;
+        }
+        writeln!(w, "distance,x,y,z,amplitude,phase,re,im")?;
+        for (i, (p, v)) in self.points.iter().zip(self.field.iter()).enumerate() {
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{}",
+                self.distance(i),
+                p[0],
+                p[1],
+                p[2],
+                self.amplitudes[i] * source_amplitude,
+                v[1].atan2(v[0]),
+                v[0] * source_amplitude,
+                v[1] * source_amplitude
+            )?;
+        }
+        w.flush()?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(amplitudes: Vec<f32>, step: f32) -> Profile {
+        let n = amplitudes.len();
+        Profile {
+            points: (0..n).map(|i| [i as f32 * step, 0.0, 0.0]).collect(),
+            field: amplitudes.iter().map(|&a| [a, 0.0]).collect(),
+            amplitudes,
+            step,
+        }
+    }
+
+    #[test]
+    fn fwhm_of_gaussian() {
+        let sigma = 5.0f32;
+        let p = profile(
+            (0..201)
+                .map(|i| {
+                    let x = (i as f32 - 100.0) * 0.5;
+                    (-x * x / (2.0 * sigma * sigma)).exp()
+                })
+                .collect(),
+            0.5,
+        );
+        let expected = 2.0 * (2.0 * 2f32.ln()).sqrt() * sigma;
+        assert!((p.fwhm().unwrap() - expected).abs() < 0.05);
+        assert_eq!(p.peak(), Some((100, 1.0)));
+        // a single lobe has no side lobe
+        assert_eq!(p.side_lobe_level(), None);
+    }
+
+    #[test]
+    fn fwhm_of_flat_top() {
+        let p = profile(vec![0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0], 1.0);
+        assert!((p.fwhm().unwrap() - 4.0).abs() < 1e-6);
+        assert_eq!(p.side_lobe_level(), None);
+    }
+
+    #[test]
+    fn peak_at_line_end() {
+        let p = profile(vec![1.0, 0.8, 0.6, 0.4, 0.2], 1.0);
+        assert_eq!(p.peak(), Some((0, 1.0)));
+        assert_eq!(p.fwhm(), None);
+        assert_eq!(p.side_lobe_level(), None);
+
+        let p = profile(vec![0.2, 0.4, 0.6, 0.8, 1.0], 1.0);
+        assert_eq!(p.fwhm(), None);
+    }
+
+    #[test]
+    fn side_lobe_level() {
+        let p = profile(vec![0.2, 0.1, 0.5, 1.0, 0.5, 0.1, 0.3, 0.2], 1.0);
+        let expected = 20.0 * 0.3f32.log10();
+        assert!((p.side_lobe_level().unwrap() - expected).abs() < 1e-4);
+        assert!((p.fwhm().unwrap() - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn empty_profile() {
+        let p = profile(vec![], 1.0);
+        assert_eq!(p.peak(), None);
+        assert_eq!(p.fwhm(), None);
+        assert_eq!(p.side_lobe_level(), None);
+    }
+}
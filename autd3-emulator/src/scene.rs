@@ -0,0 +1,266 @@
+/*
+ * File: scene.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use acoustic_field_viewer::{volume_viewer::VolumeSettings, ViewerSettings};
+use anyhow::{anyhow, Result};
+use autd3_core::{Duty, Phase, NUM_TRANS_IN_UNIT};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    contour::ContourMode, fpga_state::FpgaState, geometry_file::DeviceLayout, history::Drives,
+    slices::SliceSetting,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct DeviceScene {
+    pub layout: DeviceLayout,
+    pub state: FpgaState,
+    pub modulation: Vec<u8>,
+    // one entry per STM index; a single entry when STM is off
+    pub duties: Vec<Vec<u16>>,
+    pub phases: Vec<Vec<u16>>,
+}
+
+impl DeviceScene {
+    pub fn new(
+        layout: DeviceLayout,
+        state: FpgaState,
+        modulation: Vec<u8>,
+        drives: &[([Duty; NUM_TRANS_IN_UNIT], [Phase; NUM_TRANS_IN_UNIT])],
+    ) -> Self {
+        Self {
+            layout,
+            state,
+            modulation,
+            duties: drives
+                .iter()
+                .map(|(d, _)| d.iter().map(|d| d.duty).collect())
+                .collect(),
+            phases: drives
+                .iter()
+                .map(|(_, p)| p.iter().map(|p| p.phase).collect())
+                .collect(),
+        }
+    }
+
+    // devices without a state or drives are skipped; cycles are one per transducer of all devices
+    pub fn collect(
+        layouts: &[DeviceLayout],
+        fpga_states: &[FpgaState],
+        drives: &Drives,
+        cycles: &[u16],
+        modulations: &[(Vec<u8>, u32)],
+    ) -> Vec<Self> {
+        layouts
+            .iter()
+            .enumerate()
+            .filter_map(|(i, layout)| {
+                let mut state = fpga_states.get(i)?.clone();
+                if let Some(c) = cycles.get(i * NUM_TRANS_IN_UNIT..(i + 1) * NUM_TRANS_IN_UNIT) {
+                    state.cycles = c.to_vec();
+                }
+                let (modulation, mod_freq_div) = modulations.get(i).cloned().unwrap_or_default();
+                state.mod_size = modulation.len();
+                state.mod_freq_div = mod_freq_div;
+                Some(Self::new(layout.clone(), state, modulation, drives.get(i)?))
+            })
+            .collect()
+    }
+
+    pub fn modulation(&self) -> (Vec<u8>, u32) {
+        (self.modulation.clone(), self.state.mod_freq_div)
+    }
+
+    pub fn drives(&self) -> Vec<([Duty; NUM_TRANS_IN_UNIT], [Phase; NUM_TRANS_IN_UNIT])> {
+        self.duties
+            .iter()
+            .zip(self.phases.iter())
+            .map(|(d, p)| {
+                (
+                    std::array::from_fn(|i| Duty { duty: d[i] }),
+                    std::array::from_fn(|i| Phase { phase: p[i] }),
+                )
+            })
+            .collect()
+    }
+
+    fn validate(&self, idx: usize) -> Result<()> {
+        if self.layout.transducers.len() != NUM_TRANS_IN_UNIT
+            || self.state.cycles.len() != NUM_TRANS_IN_UNIT
+        {
+            return Err(anyhow!("device {}: invalid number of transducers", idx));
+        }
+        if self.duties.is_empty() || self.duties.len() != self.phases.len() {
+            return Err(anyhow!("device {}: invalid drive table", idx));
+        }
+        if self
+            .duties
+            .iter()
+            .chain(self.phases.iter())
+            .any(|v| v.len() != NUM_TRANS_IN_UNIT)
+        {
+            return Err(anyhow!("device {}: invalid drive table size", idx));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Scene {
+    pub devices: Vec<DeviceScene>,
+    pub static_mod: f32,
+    pub stm_idx: i32,
+    pub show: Vec<bool>,
+    pub enable: Vec<bool>,
+    pub show_axis: Vec<bool>,
+    pub viewer_setting: ViewerSettings,
+    // the fields below are missing in scenes saved before they were added
+    #[serde(default)]
+    pub color_map_path: String,
+    #[serde(default)]
+    pub slices: Vec<SliceSetting>,
+    #[serde(default)]
+    pub volume: VolumeSettings,
+    #[serde(default)]
+    pub show_contours: bool,
+    #[serde(default)]
+    pub contour_mode: ContourMode,
+    #[serde(default = "ContourMode::default_levels")]
+    pub contour_levels: Vec<f32>,
+}
+
+impl Scene {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        serde_json::to_writer(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let scene: Scene = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        for (i, dev) in scene.devices.iter().enumerate() {
+            dev.validate(i)?;
+        }
+        if scene.show.len() < scene.devices.len()
+            || scene.enable.len() < scene.devices.len()
+            || scene.show_axis.len() < scene.devices.len()
+        {
+            return Err(anyhow!("device flags are missing"));
+        }
+        Ok(scene)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use acoustic_field_viewer::volume_viewer::VolumeSettings;
+
+    use super::*;
+    use crate::server::Geometry;
+
+    fn layout(i: usize) -> DeviceLayout {
+        DeviceLayout::from_geometry(Geometry {
+            origin: [192.0 * i as f32, 0.0, 0.0],
+            right: [1.0, 0.0, 0.0],
+            up: [0.0, 1.0, 0.0],
+        })
+    }
+
+    fn state(i: usize) -> FpgaState {
+        FpgaState {
+            is_legacy_mode: false,
+            is_force_fan: i == 1,
+            is_stm_mode: false,
+            is_stm_gain_mode: false,
+            stm_freq_div: 0,
+            stm_size: 1,
+            sound_speed: 0,
+            silencer_cycle: 4096,
+            silencer_step: 10,
+            mod_size: 0,
+            mod_freq_div: 0,
+            cycles: vec![4096; NUM_TRANS_IN_UNIT],
+        }
+    }
+
+    fn drives(i: usize) -> Vec<([Duty; NUM_TRANS_IN_UNIT], [Phase; NUM_TRANS_IN_UNIT])> {
+        vec![(
+            std::array::from_fn(|t| Duty {
+                duty: (t + i) as u16,
+            }),
+            std::array::from_fn(|t| Phase {
+                phase: (2 * t + i) as u16,
+            }),
+        )]
+    }
+
+    fn modulation(i: usize) -> (Vec<u8>, u32) {
+        (
+            (0..=255u8).map(|v| v.wrapping_add(16 * i as u8)).collect(),
+            40960 + i as u32,
+        )
+    }
+
+    #[test]
+    fn save_load_save() {
+        let layouts: Vec<_> = (0..2).map(layout).collect();
+        let states: Vec<_> = (0..2).map(state).collect();
+        let drives: Drives = (0..2).map(drives).collect();
+        let modulations: Vec<_> = (0..2).map(modulation).collect();
+        let cycles: Vec<u16> = states.iter().flat_map(|s| s.cycles.clone()).collect();
+        let scene = Scene {
+            devices: DeviceScene::collect(&layouts, &states, &drives, &cycles, &modulations),
+            static_mod: 1.0,
+            stm_idx: 0,
+            show: vec![true; 2],
+            enable: vec![true; 2],
+            show_axis: vec![false; 2],
+            viewer_setting: ViewerSettings::new(),
+            color_map_path: String::new(),
+            slices: vec![],
+            volume: VolumeSettings::new(),
+            show_contours: false,
+            contour_mode: ContourMode::default(),
+            contour_levels: ContourMode::default_levels(),
+        };
+
+        let dir = std::env::temp_dir();
+        let first = dir.join("autd3_emulator_scene_first.json");
+        let second = dir.join("autd3_emulator_scene_second.json");
+        scene.save(&first).unwrap();
+
+        // restore the device state the way the viewer does when a scene is loaded
+        let loaded = Scene::load(&first).unwrap();
+        let layouts: Vec<_> = loaded.devices.iter().map(|d| d.layout.clone()).collect();
+        let states: Vec<_> = loaded.devices.iter().map(|d| d.state.clone()).collect();
+        let drives: Drives = loaded.devices.iter().map(|d| d.drives()).collect();
+        let cycles: Vec<u16> = states.iter().flat_map(|s| s.cycles.clone()).collect();
+        let modulations: Vec<_> = loaded.devices.iter().map(|d| d.modulation()).collect();
+        assert_eq!(modulations, (0..2).map(modulation).collect::<Vec<_>>());
+
+        let devices = DeviceScene::collect(&layouts, &states, &drives, &cycles, &modulations);
+        Scene { devices, ..loaded }.save(&second).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&first).unwrap(),
+            std::fs::read_to_string(&second).unwrap()
+        );
+        std::fs::remove_file(first).ok();
+        std::fs::remove_file(second).ok();
+    }
+}
@@ -4,8 +4,8 @@
  * Created Date: 09/05/2022
  * Author: Shun Suzuki
  * -----
- * Last Modified: 24/08/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2022 Hapis Lab. All rights reserved.
  *
@@ -13,7 +13,10 @@
 
 use anyhow::Result;
 
-use std::sync::mpsc::{self, Receiver};
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{self, Receiver},
+};
 
 use acoustic_field_viewer::Vector3;
 use autd3_core::{
@@ -22,9 +25,17 @@ use autd3_core::{
     NUM_TRANS_Y, TRANS_SPACING_MM,
 };
 use autd3_firmware_emulator::Emulator;
+use serde::{Deserialize, Serialize};
 
-use crate::interface::Interface;
+use crate::{
+    breakpoint::Breakpoint,
+    faults::DeviceFault,
+    interface::Interface,
+    packet::{Packet, PacketLog, MSG_EMU_POWER_CYCLE, MSG_EMU_REBOOT},
+    traffic::TrafficStats,
+};
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Geometry {
     pub origin: Vector3,
     pub right: Vector3,
@@ -59,17 +70,30 @@ pub enum AUTDEvent {
     RequestCpuVersion,
     Normal(CPUControlFlags),
     Geometries(Vec<Geometry>),
+    PowerCycle(bool),
+    Reboot(usize),
 }
 
 pub struct AUTDServer {
     _interface: Interface,
-    rx: Receiver<Vec<u8>>,
+    rx: Receiver<Packet>,
     emulator: Emulator,
     tx_buf: TxDatagram,
+    packets: PacketLog,
+    queue: VecDeque<Packet>,
+    traffic: TrafficStats,
+    paused: bool,
+    step: bool,
+    skip_breakpoint: bool,
+    breakpoints: Vec<Breakpoint>,
+    breakpoint_hit: Option<String>,
+    // (device, message) for each fault that affected the datagrams handled since the last take
+    fault_hits: Vec<(usize, String)>,
+    faults: Vec<DeviceFault>,
 }
 
 impl AUTDServer {
-    pub fn new(addr: &str) -> Result<Self> {
+    pub fn new(addr: &str, packet_max: usize) -> Result<Self> {
         let (tx, rx) = mpsc::channel();
         let mut interface = Interface::open(addr)?;
         interface.start(tx)?;
@@ -79,12 +103,23 @@ impl AUTDServer {
             rx,
             emulator: Emulator::new(),
             tx_buf: TxDatagram::new(0),
+            packets: PacketLog::new(packet_max),
+            queue: VecDeque::new(),
+            traffic: TrafficStats::new(),
+            paused: false,
+            step: false,
+            skip_breakpoint: false,
+            breakpoints: Vec::new(),
+            breakpoint_hit: None,
+            fault_hits: Vec::new(),
+            faults: Vec::new(),
         })
     }
 
-    fn set_device_num(&mut self, n: usize) {
+    pub fn set_device_num(&mut self, n: usize) {
         self.emulator.init(n);
         self.tx_buf = TxDatagram::new(n);
+        self.faults.resize_with(n, Default::default);
     }
 
     fn to_vec3(buf: &[u8]) -> Vector3 {
@@ -109,8 +144,14 @@ impl AUTDServer {
         res
     }
 
-    pub fn update<F: FnOnce(AUTDEvent, &Emulator)>(&mut self, f: F) {
-        if let Ok(raw_buf) = self.rx.try_recv() {
+    pub fn update<F: FnOnce(AUTDEvent, &Emulator, &Packet)>(&mut self, f: F) {
+        while let Ok(packet) = self.rx.try_recv() {
+            self.traffic.record(&packet);
+            self.queue.push_back(packet);
+        }
+
+        if let Some(packet) = self.next_packet() {
+            let raw_buf = &packet.raw;
             unsafe {
                 if raw_buf.len() >= std::mem::size_of::<GlobalHeader>() {
                     self.tx_buf
@@ -125,6 +166,21 @@ impl AUTDServer {
                             self.set_device_num(geometries.len());
                             AUTDEvent::Geometries(geometries)
                         }
+                        MSG_EMU_POWER_CYCLE => {
+                            let keep_geometry = raw_buf
+                                .get(std::mem::size_of::<GlobalHeader>())
+                                .map_or(true, |&v| v != 0);
+                            self.power_cycle(keep_geometry);
+                            AUTDEvent::PowerCycle(keep_geometry)
+                        }
+                        MSG_EMU_REBOOT => {
+                            let idx = match raw_buf[std::mem::size_of::<GlobalHeader>()..] {
+                                [lo, hi, ..] => u16::from_le_bytes([lo, hi]) as usize,
+                                _ => 0,
+                            };
+                            self.reboot_device(idx);
+                            AUTDEvent::Reboot(idx)
+                        }
                         MSG_CLEAR => AUTDEvent::Clear,
                         MSG_RD_CPU_VERSION => AUTDEvent::RequestCpuVersion,
                         MSG_RD_FPGA_VERSION => AUTDEvent::RequestFpgaVersion,
@@ -145,15 +201,166 @@ impl AUTDServer {
                                     src = src.add(std::mem::size_of::<Body>());
                                 });
                             self.tx_buf.num_bodies = num_bodies;
-                            self.emulator.send(&self.tx_buf);
+                            let cpu_flag = self.tx_buf.header().cpu_flag;
+                            self.fault_hits
+                                .extend(self.faults.iter().enumerate().filter_map(|(i, f)| {
+                                    f.reject_reason(cpu_flag).map(|r| (i, r.to_owned()))
+                                }));
+                            if self.faults.iter().all(|f| f.accepts(cpu_flag)) {
+                                self.emulator.send(&self.tx_buf);
+                            } else {
+                                self.emulator
+                                    .cpus_mut()
+                                    .iter_mut()
+                                    .zip(self.faults.iter())
+                                    .filter(|(_, f)| f.accepts(cpu_flag))
+                                    .for_each(|(cpu, _)| cpu.send(&self.tx_buf));
+                            }
                             AUTDEvent::Normal(self.tx_buf.header().cpu_flag)
                         }
                     };
 
-                    f(event, &self.emulator);
+                    self.update_rx();
+                    f(event, &self.emulator, &packet);
                 }
             }
+            self.packets.push(packet);
+        }
+
+        self.traffic.sample(self.queue.len());
+    }
+
+    // datagrams in flight are lost when the devices are powered off
+    pub fn power_cycle(&mut self, keep_geometry: bool) {
+        let n = if keep_geometry {
+            self.emulator.cpus().len()
+        } else {
+            0
+        };
+        self.emulator = Emulator::new();
+        self.set_device_num(n);
+        self.faults.iter_mut().for_each(|f| f.rx = [0, 0]);
+        self.queue.clear();
+    }
+
+    pub fn reboot_device(&mut self, idx: usize) {
+        if let Some(cpu) = self.emulator.cpus_mut().get_mut(idx) {
+            cpu.init();
+        }
+        if let Some(f) = self.faults.get_mut(idx) {
+            f.rx = [0, 0];
+        }
+    }
+
+    // the rx messages (ack, msg_id) the devices would report for the last datagram
+    fn update_rx(&mut self) {
+        let msg_id = self.tx_buf.header().msg_id;
+        self.faults
+            .iter_mut()
+            .zip(self.emulator.cpus().iter())
+            .enumerate()
+            .for_each(|(i, (f, cpu))| {
+                f.respond(cpu.ack(), msg_id);
+                if f.stale_msg_id && !f.offline && f.rx[1] != msg_id {
+                    self.fault_hits.push((
+                        i,
+                        format!("stale msg_id: {} reported for {}", f.rx[1], msg_id),
+                    ));
+                }
+            });
+    }
+
+    pub fn faults(&self) -> &[DeviceFault] {
+        &self.faults
+    }
+
+    pub fn faults_mut(&mut self) -> &mut [DeviceFault] {
+        &mut self.faults
+    }
+
+    pub fn emulator(&self) -> &Emulator {
+        &self.emulator
+    }
+
+    fn next_packet(&mut self) -> Option<Packet> {
+        let front = self.queue.front()?;
+        if self.paused && !self.step {
+            return None;
+        }
+        let stepping = std::mem::take(&mut self.step);
+        let skip = std::mem::take(&mut self.skip_breakpoint) || stepping;
+        if !skip {
+            let dev_num = self.emulator.cpus().len();
+            if let Some(bp) = self
+                .breakpoints
+                .iter_mut()
+                .find(|bp| bp.enabled && bp.condition.matches(front, dev_num))
+            {
+                bp.hits += 1;
+                self.paused = true;
+                // the packet that triggered the breakpoint is applied without being checked again
+                self.skip_breakpoint = true;
+                self.breakpoint_hit = Some(format!(
+                    "break before {} packet: {}",
+                    front.kind().map_or("invalid", |k| k.name()),
+                    bp.condition.description()
+                ));
+                return None;
+            }
         }
+        self.queue.pop_front()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn step(&mut self) {
+        self.step = true;
+    }
+
+    pub fn queue(&self) -> &VecDeque<Packet> {
+        &self.queue
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn breakpoints_mut(&mut self) -> &mut Vec<Breakpoint> {
+        &mut self.breakpoints
+    }
+
+    pub fn take_breakpoint_hit(&mut self) -> Option<String> {
+        self.breakpoint_hit.take()
+    }
+
+    pub fn take_fault_hits(&mut self) -> Vec<(usize, String)> {
+        std::mem::take(&mut self.fault_hits)
+    }
+
+    pub fn packets(&self) -> &PacketLog {
+        &self.packets
+    }
+
+    pub fn packets_mut(&mut self) -> &mut PacketLog {
+        &mut self.packets
+    }
+
+    pub fn traffic(&self) -> &TrafficStats {
+        &self.traffic
+    }
+
+    pub fn traffic_mut(&mut self) -> &mut TrafficStats {
+        &mut self.traffic
     }
 
     pub fn close(&mut self) -> Result<()> {
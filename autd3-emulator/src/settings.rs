@@ -4,33 +4,77 @@
  * Created Date: 05/07/2021
  * Author: Shun Suzuki
  * -----
- * Last Modified: 09/05/2022
- * Modified By: Shun Suzuki (suzuki@hapis.k.u-tokyo.ac.jp)
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
  * -----
  * Copyright (c) 2021 Hapis Lab. All rights reserved.
  *
  */
 
-use acoustic_field_viewer::{renderer::Renderer, ViewerSettings};
+use acoustic_field_viewer::{renderer::Renderer, volume_viewer::VolumeSettings, ViewerSettings};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::{
     fs::{File, OpenOptions},
-    io::Write,
+    io::{ErrorKind, Write},
     path::Path,
 };
 
+use crate::{
+    contour::ContourMode, field_export::Quantity, field_stats::ScaleMode, profile::ProfileLine,
+    slices::SliceSetting,
+};
+
+// fields missing in a setting.json written by an older version take their default values
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct Setting {
     pub port: u16,
     pub window_width: u32,
     pub window_height: u32,
     pub viewer_setting: ViewerSettings,
+    pub color_scale_mode: ScaleMode,
+    pub color_scale_percentile: f32,
+    pub color_map_path: String,
+    pub show_colorbar: bool,
+    pub colorbar_in_image: bool,
+    pub colorbar_pascal: bool,
+    // pressure amplitude [Pa] at 1 mm from a transducer driven with the maximum duty
+    pub source_amplitude: f32,
+    pub show_contours: bool,
+    pub contour_mode: ContourMode,
+    pub contour_levels: Vec<f32>,
+    pub contour_in_image: bool,
+    pub show_profile: bool,
+    pub profile_line: ProfileLine,
+    pub profile_export_path: String,
     pub log_enable: bool,
     pub log_max: u32,
+    pub log_file_enable: bool,
+    pub log_file_path: String,
+    pub log_file_max_size: u32,
+    pub log_file_count: u32,
+    pub packet_max: u32,
+    pub history_max: u32,
+    // [MiB]
+    pub history_max_size: u32,
+    pub traffic_json_path: String,
     pub show_mod_plot: bool,
     pub show_mod_plot_raw: bool,
     pub mod_plot_size: [f32; 2],
+    pub mod_wav_path: String,
+    pub mod_wav_duration: f32,
+    pub geometry_file: String,
+    pub scene_path: String,
     pub save_file_path: String,
+    pub field_export_path: String,
+    pub field_export_quantity: Quantity,
+    // slices shown in addition to the one in viewer_setting
+    pub slices: Vec<SliceSetting>,
+    pub volume: VolumeSettings,
+    pub vtk_export_path: String,
+    // compute the exported volume on the CPU instead of reading back the GPU result
+    pub vtk_cpu_solver: bool,
     pub record_path: String,
     pub show: Vec<bool>,
     pub enable: Vec<bool>,
@@ -44,17 +88,85 @@ impl Setting {
             window_width: 960,
             window_height: 640,
             viewer_setting: ViewerSettings::new(),
+            color_scale_mode: ScaleMode::Fixed,
+            color_scale_percentile: 99.0,
+            color_map_path: String::new(),
+            show_colorbar: true,
+            colorbar_in_image: false,
+            colorbar_pascal: false,
+            // T4010A1: 121.5 dB SPL at 300 mm
+            source_amplitude: 10084.0,
+            show_contours: false,
+            contour_mode: ContourMode::default(),
+            contour_levels: ContourMode::default_levels(),
+            contour_in_image: false,
+            show_profile: false,
+            profile_line: ProfileLine::new(),
+            profile_export_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("profile")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
             log_enable: true,
             log_max: 100,
+            log_file_enable: false,
+            log_file_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("log.jsonl")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            log_file_max_size: 1024,
+            log_file_count: 5,
+            packet_max: 200,
+            history_max: 100,
+            history_max_size: 64,
+            traffic_json_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("traffic.json")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
             show_mod_plot: true,
             show_mod_plot_raw: false,
             mod_plot_size: [200.0, 50.],
+            mod_wav_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("modulation.wav")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            mod_wav_duration: 1.0,
+            geometry_file: String::new(),
+            scene_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("scene.json")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
             save_file_path: std::env::current_dir()
                 .unwrap_or_default()
                 .join("image.png")
                 .to_str()
                 .unwrap_or("")
                 .to_owned(),
+            field_export_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("field")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            field_export_quantity: Quantity::Complex,
+            slices: vec![],
+            volume: VolumeSettings::new(),
+            vtk_export_path: std::env::current_dir()
+                .unwrap_or_default()
+                .join("volume")
+                .to_str()
+                .unwrap_or("")
+                .to_owned(),
+            vtk_cpu_solver: false,
             record_path: std::env::current_dir()
                 .unwrap_or_default()
                 .join("record")
@@ -74,16 +186,14 @@ impl Setting {
         self.window_height = size.height;
     }
 
-    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+    // a missing file gives the default settings, a broken one is an error
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let file = match File::open(path) {
             Ok(file) => file,
-            Err(_) => return Self::new(),
-        };
-        let setting: Self = match serde_json::from_reader(file) {
-            Ok(setting) => setting,
-            Err(_) => Self::new(),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e.into()),
         };
-        setting
+        Ok(serde_json::from_reader(file)?)
     }
 
     pub fn save<P: AsRef<Path>>(&self, path: P) {
@@ -102,3 +212,23 @@ impl Default for Setting {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_missing_in_older_settings_take_defaults() {
+        let setting: Setting =
+            serde_json::from_str(r#"{ "port": 1234, "viewer_setting": { "fov": 1.0 } }"#).unwrap();
+        let default = Setting::new();
+        assert_eq!(setting.port, 1234);
+        assert_eq!(setting.viewer_setting.fov, 1.0);
+        assert_eq!(setting.window_width, default.window_width);
+        assert_eq!(setting.history_max, default.history_max);
+        assert_eq!(
+            setting.viewer_setting.slice_width,
+            default.viewer_setting.slice_width
+        );
+    }
+}
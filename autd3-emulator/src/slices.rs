@@ -0,0 +1,173 @@
+/*
+ * File: slices.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use acoustic_field_viewer::{
+    common::color_map::{ColorMapKind, Rgb},
+    field_compute_pipeline::{FieldComputePipeline, SliceQuantity},
+    renderer::Renderer,
+    slice_viewer::SliceViewer,
+    sound_sources::SoundSources,
+    Matrix4, UpdateFlag, Vector3, Vector4, ViewerSettings,
+};
+use serde::{Deserialize, Serialize};
+use vulkano::{
+    command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer},
+    sync::GpuFuture,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct SliceSetting {
+    pub visible: bool,
+    pub width: u32,
+    pub height: u32,
+    pub pixel_size: u32,
+    pub pos: Vector4,
+    pub angle: Vector3,
+    pub quantity: SliceQuantity,
+    pub color_map: ColorMapKind,
+    pub color_scale: f32,
+    pub db_mode: bool,
+    pub db_range: f32,
+    pub alpha: f32,
+}
+
+impl SliceSetting {
+    pub fn from_viewer_setting(s: &ViewerSettings) -> Self {
+        Self {
+            visible: true,
+            width: s.slice_width,
+            height: s.slice_height,
+            pixel_size: s.slice_pixel_size,
+            pos: s.slice_pos,
+            angle: s.slice_angle,
+            quantity: s.slice_quantity,
+            color_map: s.color_map,
+            color_scale: s.color_scale,
+            db_mode: s.db_mode,
+            db_range: s.db_range,
+            alpha: s.slice_alpha,
+        }
+    }
+
+    // the viewer setting this slice is computed and drawn with
+    pub fn viewer_setting(&self, base: &ViewerSettings) -> ViewerSettings {
+        ViewerSettings {
+            slice_width: self.width,
+            slice_height: self.height,
+            slice_pixel_size: self.pixel_size,
+            slice_pos: self.pos,
+            slice_angle: self.angle,
+            slice_quantity: self.quantity,
+            color_map: self.color_map,
+            color_scale: self.color_scale,
+            db_mode: self.db_mode,
+            db_range: self.db_range,
+            slice_alpha: self.alpha,
+            ..*base
+        }
+    }
+}
+
+pub struct Slice {
+    pub setting: SliceSetting,
+    pub viewer: SliceViewer,
+    pipeline: FieldComputePipeline,
+    dirty: UpdateFlag,
+    compute_pending: bool,
+}
+
+impl Slice {
+    pub fn new(
+        renderer: &Renderer,
+        setting: SliceSetting,
+        base: &ViewerSettings,
+        custom_color_map: &[Rgb],
+    ) -> Self {
+        let s = setting.viewer_setting(base);
+        let mut viewer = SliceViewer::new(renderer, &s);
+        viewer.move_to(s.slice_pos);
+        viewer.rotate_to(s.slice_angle);
+        let mut pipeline = FieldComputePipeline::new(renderer.queue(), &s);
+        pipeline.set_custom_color_map(custom_color_map.to_vec());
+        Self {
+            setting,
+            viewer,
+            pipeline,
+            dirty: UpdateFlag::all(),
+            compute_pending: false,
+        }
+    }
+
+    // flags changed from the UI of this slice, applied on the next update
+    pub fn mark(&mut self, flag: UpdateFlag) {
+        if flag.contains(UpdateFlag::UPDATE_SLICE_POS) {
+            self.viewer.move_to(self.setting.pos);
+            self.viewer.rotate_to(self.setting.angle);
+        }
+        self.dirty |= flag;
+    }
+
+    pub fn set_custom_color_map(&mut self, colors: Vec<Rgb>) {
+        self.pipeline.set_custom_color_map(colors);
+        if self.setting.color_map == ColorMapKind::Custom {
+            self.dirty |= UpdateFlag::UPDATE_COLOR_MAP;
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        view_projection: &(Matrix4, Matrix4),
+        base: &ViewerSettings,
+        sources: &SoundSources,
+        update_flag: UpdateFlag,
+    ) {
+        let flag = update_flag | self.dirty;
+        self.dirty = UpdateFlag::empty();
+        let s = self.setting.viewer_setting(base);
+        self.viewer.update(renderer, view_projection, &s, flag);
+        self.pipeline.update(sources, flag, &s);
+        self.compute_pending |= flag.intersects(
+            UpdateFlag::INIT_SOURCE
+                | UpdateFlag::UPDATE_COLOR_MAP
+                | UpdateFlag::UPDATE_SLICE_POS
+                | UpdateFlag::UPDATE_SLICE_SIZE
+                | UpdateFlag::UPDATE_SOURCE_DRIVE
+                | UpdateFlag::UPDATE_SOURCE_FLAG,
+        );
+    }
+
+    pub fn compute(
+        &mut self,
+        sources: &SoundSources,
+        base: &ViewerSettings,
+    ) -> Option<Box<dyn GpuFuture>> {
+        if !self.compute_pending || sources.is_empty() {
+            return None;
+        }
+        self.compute_pending = false;
+        Some(self.pipeline.compute(
+            self.viewer.field_image_view(),
+            self.viewer.field(),
+            self.viewer.model(),
+            sources,
+            &self.setting.viewer_setting(base),
+        ))
+    }
+
+    pub fn render(&mut self, builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) {
+        if self.setting.visible {
+            self.viewer.render(builder);
+        }
+    }
+}
@@ -0,0 +1,362 @@
+/*
+ * File: traffic.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use autd3_core::CPUControlFlags;
+use serde::Serialize;
+
+use crate::packet::{EventKind, Packet};
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+const HISTORY_LEN: usize = 256;
+// bin i holds datagrams of [2^i, 2^(i+1)) bytes, bin 0 also empty ones; UDP datagrams are smaller than 64 KiB
+const SIZE_BINS: usize = 17;
+
+struct Record {
+    time: Instant,
+    kind: Option<EventKind>,
+    size: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct KindStats {
+    pub kind: &'static str,
+    pub count: u64,
+    pub rate: f32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SizeBin {
+    pub min: usize,
+    pub max: usize,
+    pub count: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct IntervalStats {
+    pub last_ms: f32,
+    pub mean_ms: f32,
+    pub min_ms: f32,
+    pub max_ms: f32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TrafficSummary {
+    pub elapsed: f32,
+    pub total_packets: u64,
+    pub total_bytes: u64,
+    pub invalid_packets: u64,
+    pub packets_per_sec: f32,
+    pub bytes_per_sec: f32,
+    pub kinds: Vec<KindStats>,
+    pub size_histogram: Vec<SizeBin>,
+    pub drive_update_interval: Option<IntervalStats>,
+    pub backlog: usize,
+    pub max_backlog: usize,
+}
+
+pub struct TrafficStats {
+    start: Instant,
+    counts: [u64; EventKind::ALL.len()],
+    invalid: u64,
+    total_bytes: u64,
+    size_histogram: [u64; SIZE_BINS],
+    recent: VecDeque<Record>,
+    last_drive_update: Option<Instant>,
+    drive_intervals: VecDeque<f32>,
+    backlog: usize,
+    max_backlog: usize,
+    packets_per_sec_history: VecDeque<f32>,
+    bytes_per_sec_history: VecDeque<f32>,
+    backlog_history: VecDeque<f32>,
+}
+
+impl TrafficStats {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            counts: [0; EventKind::ALL.len()],
+            invalid: 0,
+            total_bytes: 0,
+            size_histogram: [0; SIZE_BINS],
+            recent: VecDeque::new(),
+            last_drive_update: None,
+            drive_intervals: VecDeque::new(),
+            backlog: 0,
+            max_backlog: 0,
+            packets_per_sec_history: VecDeque::new(),
+            bytes_per_sec_history: VecDeque::new(),
+            backlog_history: VecDeque::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn record(&mut self, packet: &Packet) {
+        let now = Instant::now();
+        let size = packet.raw.len();
+        let kind = packet.kind();
+        match kind {
+            Some(k) => self.counts[k as usize] += 1,
+            None => self.invalid += 1,
+        }
+        self.total_bytes += size as u64;
+        self.size_histogram[Self::size_bin(size)] += 1;
+
+        let is_drive_update = kind == Some(EventKind::Normal)
+            && packet
+                .cpu_flag()
+                .map_or(false, |f| f.contains(CPUControlFlags::WRITE_BODY));
+        if is_drive_update {
+            if let Some(last) = self.last_drive_update {
+                Self::push_history(
+                    &mut self.drive_intervals,
+                    now.duration_since(last).as_secs_f32() * 1000.0,
+                );
+            }
+            self.last_drive_update = Some(now);
+        }
+
+        self.recent.push_back(Record {
+            time: now,
+            kind,
+            size,
+        });
+    }
+
+    // called once per server update to advance the live plots
+    pub fn sample(&mut self, backlog: usize) {
+        let now = Instant::now();
+        while let Some(r) = self.recent.front() {
+            if now.duration_since(r.time) <= RATE_WINDOW {
+                break;
+            }
+            self.recent.pop_front();
+        }
+        self.backlog = backlog;
+        self.max_backlog = self.max_backlog.max(backlog);
+
+        let packets_per_sec = self.packets_per_sec();
+        let bytes_per_sec = self.bytes_per_sec();
+        Self::push_history(&mut self.packets_per_sec_history, packets_per_sec);
+        Self::push_history(&mut self.bytes_per_sec_history, bytes_per_sec);
+        Self::push_history(&mut self.backlog_history, backlog as f32);
+    }
+
+    pub fn count(&self, kind: EventKind) -> u64 {
+        self.counts[kind as usize]
+    }
+
+    pub fn rate(&self, kind: EventKind) -> f32 {
+        self.recent.iter().filter(|r| r.kind == Some(kind)).count() as f32 / self.window_secs()
+    }
+
+    pub fn packets_per_sec(&self) -> f32 {
+        self.recent.len() as f32 / self.window_secs()
+    }
+
+    pub fn bytes_per_sec(&self) -> f32 {
+        self.recent.iter().map(|r| r.size).sum::<usize>() as f32 / self.window_secs()
+    }
+
+    pub fn backlog(&self) -> usize {
+        self.backlog
+    }
+
+    pub fn size_histogram(&self) -> Vec<f32> {
+        self.size_histogram.iter().map(|&c| c as f32).collect()
+    }
+
+    pub fn drive_intervals(&self) -> Vec<f32> {
+        self.drive_intervals.iter().copied().collect()
+    }
+
+    pub fn packets_per_sec_history(&self) -> Vec<f32> {
+        self.packets_per_sec_history.iter().copied().collect()
+    }
+
+    pub fn bytes_per_sec_history(&self) -> Vec<f32> {
+        self.bytes_per_sec_history.iter().copied().collect()
+    }
+
+    pub fn backlog_history(&self) -> Vec<f32> {
+        self.backlog_history.iter().copied().collect()
+    }
+
+    pub fn summary(&self) -> TrafficSummary {
+        let drive_update_interval = self.drive_intervals.back().map(|&last_ms| {
+            let n = self.drive_intervals.len() as f32;
+            IntervalStats {
+                last_ms,
+                mean_ms: self.drive_intervals.iter().sum::<f32>() / n,
+                min_ms: self
+                    .drive_intervals
+                    .iter()
+                    .copied()
+                    .fold(f32::MAX, f32::min),
+                max_ms: self.drive_intervals.iter().copied().fold(0.0, f32::max),
+            }
+        });
+        TrafficSummary {
+            elapsed: self.start.elapsed().as_secs_f32(),
+            total_packets: self.counts.iter().sum::<u64>() + self.invalid,
+            total_bytes: self.total_bytes,
+            invalid_packets: self.invalid,
+            packets_per_sec: self.packets_per_sec(),
+            bytes_per_sec: self.bytes_per_sec(),
+            kinds: EventKind::ALL
+                .iter()
+                .map(|&k| KindStats {
+                    kind: k.name(),
+                    count: self.count(k),
+                    rate: self.rate(k),
+                })
+                .collect(),
+            size_histogram: self
+                .size_histogram
+                .iter()
+                .enumerate()
+                .map(|(i, &count)| {
+                    let (min, max) = Self::size_bin_range(i);
+                    SizeBin { min, max, count }
+                })
+                .collect(),
+            drive_update_interval,
+            backlog: self.backlog,
+            max_backlog: self.max_backlog,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.summary())
+    }
+
+    fn window_secs(&self) -> f32 {
+        self.start
+            .elapsed()
+            .min(RATE_WINDOW)
+            .as_secs_f32()
+            .max(f32::EPSILON)
+    }
+
+    fn size_bin(size: usize) -> usize {
+        if size == 0 {
+            return 0;
+        }
+        ((usize::BITS - 1 - size.leading_zeros()) as usize).min(SIZE_BINS - 1)
+    }
+
+    // bin 0 also holds empty datagrams
+    fn size_bin_range(bin: usize) -> (usize, usize) {
+        let min = if bin == 0 { 0 } else { 1 << bin };
+        (min, (1 << (bin + 1)) - 1)
+    }
+
+    fn push_history(history: &mut VecDeque<f32>, v: f32) {
+        history.push_back(v);
+        while history.len() > HISTORY_LEN {
+            history.pop_front();
+        }
+    }
+}
+
+impl Default for TrafficStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use autd3_core::{Body, GlobalHeader, MSG_CLEAR};
+    use serde_json::Value;
+
+    use super::*;
+
+    fn packet(msg_id: u8, len: usize) -> Packet {
+        let mut raw = vec![0; len];
+        raw[0] = msg_id;
+        Packet::new(raw)
+    }
+
+    #[test]
+    fn summary_json() {
+        let header = std::mem::size_of::<GlobalHeader>();
+        let normal = (0..=u8::MAX)
+            .find(|&id| EventKind::from_msg_id(id) == EventKind::Normal)
+            .unwrap();
+        let packets = [
+            Packet::new(vec![]),
+            packet(MSG_CLEAR, header),
+            packet(normal, header + std::mem::size_of::<Body>()),
+            packet(normal, header + std::mem::size_of::<Body>()),
+        ];
+
+        let mut stats = TrafficStats::new();
+        packets.iter().for_each(|p| stats.record(p));
+        stats.sample(3);
+
+        let json: Value = serde_json::from_str(&stats.to_json().unwrap()).unwrap();
+        assert_eq!(json["total_packets"], 4);
+        assert_eq!(json["invalid_packets"], 1);
+        assert_eq!(
+            json["total_bytes"],
+            packets.iter().map(|p| p.raw.len()).sum::<usize>()
+        );
+        assert_eq!(json["backlog"], 3);
+        assert_eq!(json["max_backlog"], 3);
+        assert!(json["drive_update_interval"].is_null());
+
+        let count = |kind: EventKind| {
+            json["kinds"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|k| k["kind"] == kind.name())
+                .unwrap()["count"]
+                .clone()
+        };
+        assert_eq!(count(EventKind::Clear), 1);
+        assert_eq!(count(EventKind::Normal), 2);
+        assert_eq!(count(EventKind::Geometries), 0);
+
+        let bins = json["size_histogram"].as_array().unwrap();
+        assert_eq!(bins.len(), SIZE_BINS);
+        assert_eq!(bins[0]["min"], 0);
+        assert_eq!(bins[0]["max"], 1);
+        for w in bins.windows(2) {
+            assert_eq!(
+                w[0]["max"].as_u64().unwrap() + 1,
+                w[1]["min"].as_u64().unwrap()
+            );
+        }
+        for p in packets.iter() {
+            let size = p.raw.len() as u64;
+            let bin = bins
+                .iter()
+                .find(|b| b["min"].as_u64().unwrap() <= size && size <= b["max"].as_u64().unwrap())
+                .unwrap();
+            let expected = packets
+                .iter()
+                .filter(|q| q.raw.len() == p.raw.len())
+                .count();
+            assert_eq!(bin["count"], expected);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+/*
+ * File: vtk_export.rs
+ * Project: src
+ * Created Date: 18/10/2026
+ * Author: agent
+ * -----
+ * Last Modified: 18/10/2026
+ * Modified By: agent (agent@local)
+ * -----
+ * Copyright (c) 2026 Hapis Lab. All rights reserved.
+ *
+ */
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use acoustic_field_viewer::{sound_sources::SoundSources, volume_viewer::VolumeSettings, Vector2};
+use anyhow::{anyhow, Result};
+
+// the same model as the compute shader, for when the volume is not computed on the GPU
+pub fn solve(sources: &SoundSources, volume: &VolumeSettings) -> Vec<Vector2> {
+    let [w, h, d] = volume.dims();
+    (0..d)
+        .flat_map(|k| (0..h).flat_map(move |j| (0..w).map(move |i| (i, j, k))))
+        .map(|(i, j, k)| sources.pressure_at(volume.position(i, j, k)))
+        .collect()
+}
+
+// raw little endian arrays appended after the XML, each prefixed with its size in bytes;
+// the data part of a large volume is several times smaller than ASCII and much faster to write
+#[derive(Default)]
+struct AppendedData {
+    arrays: Vec<Vec<u8>>,
+    offset: u64,
+}
+
+impl AppendedData {
+    fn array(
+        &mut self,
+        w: &mut impl Write,
+        ty: &str,
+        name: &str,
+        components: usize,
+        bytes: Vec<u8>,
+    ) -> Result<()> {
+        writeln!(
+            w,
+            r#"        <DataArray type="{}" Name="{}" NumberOfComponents="{}" format="appended" offset="{}"/>"#,
+            ty, name, components, self.offset
+        )?;
+        self.offset += (std::mem::size_of::<u64>() + bytes.len()) as u64;
+        self.arrays.push(bytes);
+        Ok(())
+    }
+
+    fn write(self, w: &mut impl Write) -> Result<()> {
+        writeln!(w, r#"  <AppendedData encoding="raw">"#)?;
+        write!(w, "   _")?;
+        for bytes in self.arrays {
+            w.write_all(&(bytes.len() as u64).to_le_bytes())?;
+            w.write_all(&bytes)?;
+        }
+        writeln!(w)?;
+        writeln!(w, "  </AppendedData>")?;
+        Ok(())
+    }
+}
+
+fn f32s(values: impl Iterator<Item = f32>) -> Vec<u8> {
+    values.flat_map(f32::to_le_bytes).collect()
+}
+
+fn i32s(values: impl Iterator<Item = i32>) -> Vec<u8> {
+    values.flat_map(i32::to_le_bytes).collect()
+}
+
+fn header(w: &mut impl Write, ty: &str) -> Result<()> {
+    writeln!(w, r#"<?xml version="1.0"?>"#)?;
+    writeln!(
+        w,
+        r#"<VTKFile type="{}" version="1.0" byte_order="LittleEndian" header_type="UInt64">"#,
+        ty
+    )?;
+    Ok(())
+}
+
+// VTK image data; pressure is in Pa, scaled by source_amplitude
+pub fn save_vti<P: AsRef<Path>>(
+    path: P,
+    volume: &VolumeSettings,
+    field: &[Vector2],
+    source_amplitude: f32,
+) -> Result<PathBuf> {
+    let [w, h, d] = volume.dims();
+    if field.len() != w as usize * h as usize * d as usize {
+        return Err(anyhow!("field size does not match the volume size"));
+    }
+    let path = path.as_ref().with_extension("vti");
+    let mut f = BufWriter::new(File::create(&path)?);
+    let extent = format!("0 {} 0 {} 0 {}", w - 1, h - 1, d - 1);
+    let origin = volume.position(0, 0, 0);
+    let spacing = volume.effective_voxel_size();
+    header(&mut f, "ImageData")?;
+    writeln!(
+        f,
+        r#"  <ImageData WholeExtent="{}" Origin="{} {} {}" Spacing="{} {} {}">"#,
+        extent, origin[0], origin[1], origin[2], spacing, spacing, spacing
+    )?;
+    writeln!(f, r#"    <Piece Extent="{}">"#, extent)?;
+    writeln!(f, r#"      <PointData Scalars="amplitude">"#)?;
+    let mut data = AppendedData::default();
+    data.array(
+        &mut f,
+        "Float32",
+        "amplitude",
+        1,
+        f32s(field.iter().map(|v| v[0].hypot(v[1]) * source_amplitude)),
+    )?;
+    data.array(
+        &mut f,
+        "Float32",
+        "phase",
+        1,
+        f32s(field.iter().map(|v| v[1].atan2(v[0]))),
+    )?;
+    data.array(
+        &mut f,
+        "Float32",
+        "pressure",
+        2,
+        f32s(
+            field
+                .iter()
+                .flat_map(|v| [v[0], v[1]].map(|p| p * source_amplitude)),
+        ),
+    )?;
+    writeln!(f, "      </PointData>")?;
+    writeln!(f, "    </Piece>")?;
+    writeln!(f, "  </ImageData>")?;
+    data.write(&mut f)?;
+    writeln!(f, "</VTKFile>")?;
+    f.flush()?;
+    Ok(path)
+}
+
+// VTK poly data of the transducers as vertices with their normals and drives
+pub fn save_vtp<P: AsRef<Path>>(path: P, sources: &SoundSources) -> Result<PathBuf> {
+    let path = path.as_ref().with_extension("vtp");
+    let mut f = BufWriter::new(File::create(&path)?);
+    let n = sources.len();
+    header(&mut f, "PolyData")?;
+    writeln!(f, "  <PolyData>")?;
+    writeln!(
+        f,
+        r#"    <Piece NumberOfPoints="{}" NumberOfVerts="{}" NumberOfLines="0" NumberOfStrips="0" NumberOfPolys="0">"#,
+        n, n
+    )?;
+    let mut data = AppendedData::default();
+    writeln!(f, "      <Points>")?;
+    data.array(
+        &mut f,
+        "Float32",
+        "position",
+        3,
+        f32s(sources.positions().flat_map(|p| [p[0], p[1], p[2]])),
+    )?;
+    writeln!(f, "      </Points>")?;
+    writeln!(f, "      <Verts>")?;
+    data.array(&mut f, "Int32", "connectivity", 1, i32s(0..n as i32))?;
+    data.array(&mut f, "Int32", "offsets", 1, i32s(1..=n as i32))?;
+    writeln!(f, "      </Verts>")?;
+    writeln!(f, r#"      <PointData Normals="normal">"#)?;
+    data.array(
+        &mut f,
+        "Float32",
+        "normal",
+        3,
+        f32s(
+            sources
+                .position_dirs()
+                .flat_map(|(_, d)| [d[0], d[1], d[2]]),
+        ),
+    )?;
+    data.array(
+        &mut f,
+        "Float32",
+        "amplitude",
+        1,
+        f32s(sources.drives().map(|d| d.amp)),
+    )?;
+    data.array(
+        &mut f,
+        "Float32",
+        "phase",
+        1,
+        f32s(sources.drives().map(|d| d.phase)),
+    )?;
+    data.array(
+        &mut f,
+        "Float32",
+        "enable",
+        1,
+        f32s(sources.drives().map(|d| d.enable)),
+    )?;
+    writeln!(f, "      </PointData>")?;
+    writeln!(f, "    </Piece>")?;
+    writeln!(f, "  </PolyData>")?;
+    data.write(&mut f)?;
+    writeln!(f, "</VTKFile>")?;
+    f.flush()?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(xml: &str, name: &str) -> Vec<f32> {
+        let start = xml.find(&format!(r#" {}=""#, name)).unwrap() + name.len() + 3;
+        let end = start + xml[start..].find('"').unwrap();
+        xml[start..end]
+            .split_whitespace()
+            .map(|v| v.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn vti_grid_matches_volume() {
+        let volume = VolumeSettings {
+            center: [10.0, -4.0, 150.0],
+            size: [10.0, 6.0, 5.0],
+            voxel_size: 2,
+            ..VolumeSettings::new()
+        };
+        let [w, h, d] = volume.dims();
+        assert_eq!([w, h, d], [5, 3, 2]);
+        let field = vec![[0.0, 0.0]; (w * h * d) as usize];
+
+        let path = std::env::temp_dir().join("autd3_emulator_vti_grid");
+        let path = save_vti(&path, &volume, &field, 1.0).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let tag = b"<AppendedData encoding=\"raw\">\n   _";
+        let xml_end = bytes.windows(tag.len()).position(|w| w == tag).unwrap() + tag.len() - 1;
+        let xml = String::from_utf8_lossy(&bytes[..xml_end]);
+        assert_eq!(
+            attribute(&xml, "WholeExtent"),
+            [0.0, 4.0, 0.0, 2.0, 0.0, 1.0]
+        );
+        let origin = attribute(&xml, "Origin");
+        let spacing = attribute(&xml, "Spacing");
+        assert_eq!(origin, volume.position(0, 0, 0));
+        let last = volume.position(w - 1, h - 1, d - 1);
+        for (axis, n) in [w, h, d].into_iter().enumerate() {
+            let expected = origin[axis] + spacing[axis] * (n - 1) as f32;
+            assert!((expected - last[axis]).abs() < 1e-4);
+        }
+
+        // amplitude, phase and 2-component pressure, each with a u64 size prefix
+        let n = (w * h * d) as usize;
+        let data_len = 3 * 8 + 4 * n * (1 + 1 + 2);
+        let tail = b"\n  </AppendedData>\n</VTKFile>\n".len();
+        assert_eq!(bytes.len() - xml_end - 1 - tail, data_len);
+    }
+}